@@ -0,0 +1,63 @@
+//! Thread scaling of block-parallel encode/decode.
+//!
+//! `cargo bench -p ansx --features parallel` prints throughput per thread
+//! count on 64 MiB of skewed bytes (set `ANSX_BENCH_MB` to change the size).
+
+use ansx::{DecodeOptions, EncodeOptions, Interleave, decode_parallel_with, encode_parallel_with};
+use std::time::Instant;
+
+fn skewed(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            ((seed >> 32) as u8).leading_zeros() as u8
+        })
+        .collect()
+}
+
+/// Best of `runs` timings of `f`, in seconds.
+fn best<T>(runs: usize, mut f: impl FnMut() -> T) -> f64 {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(f());
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    let mb: usize = std::env::var("ANSX_BENCH_MB").ok().and_then(|v| v.parse().ok()).unwrap_or(64);
+    let data = skewed(mb << 20, 0x9E37_79B9_7F4A_7C15);
+    let enc_opts = EncodeOptions::new().interleave(Interleave::Eight);
+    let dec_opts = DecodeOptions::new();
+    let reference = ansx::encode_with(&data, &enc_opts);
+
+    let max_threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    let mut counts = vec![1];
+    while counts.last().unwrap() * 2 <= max_threads {
+        counts.push(counts.last().unwrap() * 2);
+    }
+    if *counts.last().unwrap() != max_threads {
+        counts.push(max_threads);
+    }
+
+    println!("{mb} MiB input, {} bytes encoded", reference.len());
+    println!("{:>8} {:>12} {:>12} {:>9}", "threads", "enc MB/s", "dec MB/s", "speedup");
+    let mut base = None;
+    for threads in counts {
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap();
+        let (enc, dec) = pool.install(|| {
+            let encoded = encode_parallel_with(&data, &enc_opts);
+            assert_eq!(encoded, reference, "parallel output differs from serial");
+            let enc = best(3, || encode_parallel_with(&data, &enc_opts));
+            let dec = best(3, || decode_parallel_with(&reference, &dec_opts).unwrap());
+            (enc, dec)
+        });
+        let base = *base.get_or_insert(enc + dec);
+        let mbps = |secs: f64| data.len() as f64 / secs / 1e6;
+        println!("{threads:>8} {:>12.1} {:>12.1} {:>8.2}x", mbps(enc), mbps(dec), base / (enc + dec));
+    }
+}
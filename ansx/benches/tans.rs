@@ -0,0 +1,61 @@
+//! tANS versus rANS on the same data.
+//!
+//! `cargo bench -p ansx --bench tans` prints size and throughput per backend
+//! on 32 MiB inputs (set `ANSX_BENCH_MB` to change the size).
+
+use ansx::{Backend, DecodeOptions, EncodeOptions, Interleave, decode_with, encode_with};
+use std::time::Instant;
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+/// Best of `runs` timings of `f`, in seconds.
+fn best<T>(runs: usize, mut f: impl FnMut() -> T) -> f64 {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(f());
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+fn main() {
+    let mb: usize = std::env::var("ANSX_BENCH_MB").ok().and_then(|v| v.parse().ok()).unwrap_or(32);
+    let len = mb << 20;
+    // Run values as `ledgerizer` emits them: a handful of byte values.
+    const RUN_VALUES: [u8; 8] = [0, 0, 0, 0xFF, 0xFF, 0x20, 0x0A, 0x41];
+    let inputs = [
+        ("skewed", random_bytes(len, 1).iter().map(|b| b.leading_zeros() as u8).collect::<Vec<_>>()),
+        ("run values", random_bytes(len, 2).iter().map(|b| RUN_VALUES[*b as usize & 7]).collect()),
+        ("uniform", random_bytes(len, 3)),
+    ];
+    let backends = [
+        ("rANS x1", EncodeOptions::new()),
+        ("rANS x8", EncodeOptions::new().interleave(Interleave::Eight)),
+        ("tANS", EncodeOptions::new().backend(Backend::Tans)),
+    ];
+    let dec = DecodeOptions::new();
+
+    println!("{mb} MiB per input");
+    println!("{:<12} {:<8} {:>7} {:>10} {:>10}", "data", "backend", "ratio", "enc MB/s", "dec MB/s");
+    for (name, data) in &inputs {
+        for (backend, options) in &backends {
+            let encoded = encode_with(data, options);
+            assert_eq!(&decode_with(&encoded, &dec).unwrap(), data);
+            let enc_s = best(3, || encode_with(data, options));
+            let dec_s = best(3, || decode_with(&encoded, &dec).unwrap());
+            let mbps = |secs: f64| data.len() as f64 / secs / 1e6;
+            let ratio = encoded.len() as f64 / data.len() as f64;
+            println!("{name:<12} {backend:<8} {ratio:>7.4} {:>10.1} {:>10.1}", mbps(enc_s), mbps(dec_s));
+        }
+    }
+}
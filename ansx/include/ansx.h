@@ -1,14 +1,77 @@
 #pragma once
+#include <stddef.h>
 #include <stdint.h>
 #ifdef __cplusplus
 extern "C" {
 #endif
 
-// Identity ANS-X encode/decode (pass-through)
+// ANS-X order-0 rANS encode/decode. Returned buffers are owned by the caller
+// and must be released with ansx_free(ptr, out_len). ansx_decode returns NULL
+// (and sets *out_len to 0) on any failure; ansx_decode2 reports the reason.
+// ansx_encode also returns NULL with *out_len = 0 when its output might not
+// fit in uint32_t (ansx_max_encoded_len(len) == 0); use the *64 functions
+// below for inputs that large.
 uint8_t* ansx_encode(const uint8_t* input, uint32_t len, uint32_t* out_len);
 uint8_t* ansx_decode(const uint8_t* input, uint32_t len, uint32_t* out_len);
 void ansx_free(void* ptr, uint32_t len);
 
+// Status codes returned by ansx_decode2.
+#define ANSX_OK                   0  // success
+#define ANSX_ERR_INVALID_ARGUMENT 1  // a required pointer argument is NULL
+#define ANSX_ERR_TRUNCATED        2  // input ends before the stream is complete
+#define ANSX_ERR_BAD_HEADER       3  // malformed chunk header, method byte or table
+#define ANSX_ERR_CORRUPT          4  // coded data does not decode consistently
+#define ANSX_ERR_CHECKSUM         5  // a block fails its CRC-32 check
+#define ANSX_ERR_OUTPUT_TOO_LARGE 6  // output size does not fit in the length type
+#define ANSX_ERR_BUFFER_TOO_SMALL 7  // caller buffer too small; *out_len = required size
+
+// Decodes like ansx_decode. On ANSX_OK, *out holds *out_len bytes to release
+// with ansx_free(*out, *out_len); on error *out is NULL and *out_len is 0.
+int ansx_decode2(const uint8_t* input, uint32_t len, uint8_t** out, uint32_t* out_len);
+
+// Caller-owned buffers: no allocation crosses the ABI, so no ansx_free.
+// ansx_max_encoded_len returns a capacity that always fits the encoded output
+// (0 if that bound exceeds uint32_t). Both calls return a status code and set
+// *out_len to the result size; on ANSX_ERR_BUFFER_TOO_SMALL it holds the size
+// needed instead. out may be NULL when out_cap is 0, to query that size.
+uint32_t ansx_max_encoded_len(uint32_t len);
+int ansx_encode_into(const uint8_t* input, uint32_t len, uint8_t* out, uint32_t out_cap, uint32_t* out_len);
+int ansx_decode_into(const uint8_t* input, uint32_t len, uint8_t* out, uint32_t out_cap, uint32_t* out_len);
+
+// size_t variants of the functions above, for buffers of 4 GiB and more.
+// Buffers from ansx_encode64/ansx_decode64/ansx_decode2_64 are released with
+// ansx_free64. ansx_max_encoded_len64 returns 0 only if the bound overflows.
+uint8_t* ansx_encode64(const uint8_t* input, size_t len, size_t* out_len);
+uint8_t* ansx_decode64(const uint8_t* input, size_t len, size_t* out_len);
+int ansx_decode2_64(const uint8_t* input, size_t len, uint8_t** out, size_t* out_len);
+void ansx_free64(void* ptr, size_t len);
+size_t ansx_max_encoded_len64(size_t len);
+int ansx_encode_into64(const uint8_t* input, size_t len, uint8_t* out, size_t out_cap, size_t* out_len);
+int ansx_decode_into64(const uint8_t* input, size_t len, uint8_t* out, size_t out_cap, size_t* out_len);
+
+// Streaming handles for inputs larger than memory. Feed input with *_write and
+// drain output with *_read, which stores the number of bytes copied (0 when
+// nothing is pending) in *out_len. Functions return the status codes above.
+//
+// Encoder: call ansx_encoder_finish after the last write, then read until
+// *out_len is 0. Decoder: ansx_decoder_read returns fewer than cap bytes when
+// it needs more input; ansx_decoder_finish reports ANSX_ERR_TRUNCATED if the
+// input stopped inside a chunk. Decoder errors are sticky.
+typedef struct ansx_encoder ansx_encoder;
+typedef struct ansx_decoder ansx_decoder;
+
+ansx_encoder* ansx_encoder_new(void);
+int ansx_encoder_write(ansx_encoder* enc, const uint8_t* input, size_t len);
+int ansx_encoder_finish(ansx_encoder* enc);
+int ansx_encoder_read(ansx_encoder* enc, uint8_t* out, size_t cap, size_t* out_len);
+void ansx_encoder_free(ansx_encoder* enc);
+
+ansx_decoder* ansx_decoder_new(void);
+int ansx_decoder_write(ansx_decoder* dec, const uint8_t* input, size_t len);
+int ansx_decoder_read(ansx_decoder* dec, uint8_t* out, size_t cap, size_t* out_len);
+int ansx_decoder_finish(ansx_decoder* dec);
+void ansx_decoder_free(ansx_decoder* dec);
+
 #ifdef __cplusplus
 }
-#endif 
\ No newline at end of file
+#endif
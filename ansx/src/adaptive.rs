@@ -0,0 +1,145 @@
+//! Adaptive order-0 byte model.
+//!
+//! For data with no μ/σ (such as `ledgerizer` runs) the table is learned on
+//! the fly instead of being sent. Encoder and decoder start from the same flat
+//! counts and apply the same update after every symbol, so they stay in
+//! lockstep without any table in the stream.
+//!
+//! Counts are renormalized into a fresh [`FreqTable`] every `interval`
+//! symbols. The interval starts short so the model learns quickly, and doubles
+//! up to [`MAX_INTERVAL`]. Once the counts total more than [`COUNT_LIMIT`]
+//! they are halved, so older statistics fade and the model tracks drift.
+
+use crate::error::{AnsxError, Result};
+use crate::rans::{FreqTable, InterleavedDecoder, InterleavedEncoder};
+
+/// Count added to a symbol each time it is coded.
+pub const INCREMENT: u32 = 32;
+/// Counts are halved once their total exceeds this.
+pub const COUNT_LIMIT: u32 = 1 << 14;
+/// Symbols coded before the first table rebuild.
+pub const FIRST_INTERVAL: u32 = 16;
+/// Longest gap between table rebuilds.
+pub const MAX_INTERVAL: u32 = 256;
+
+/// Online-updating frequency model over the byte alphabet.
+#[derive(Debug, Clone)]
+pub struct AdaptiveModel {
+    counts: [u32; 256],
+    total: u32,
+    table: FreqTable,
+    interval: u32,
+    until_rebuild: u32,
+}
+
+impl Default for AdaptiveModel {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl AdaptiveModel {
+    /// Creates a model with every byte equally likely.
+    pub fn new() -> Self {
+        let counts = [1; 256];
+        Self {
+            counts,
+            total: 256,
+            table: FreqTable::from_counts(&counts).expect("flat counts are valid"),
+            interval: FIRST_INTERVAL,
+            until_rebuild: FIRST_INTERVAL,
+        }
+    }
+
+    /// Table to code the next symbol with.
+    #[inline]
+    pub fn table(&self) -> &FreqTable {
+        &self.table
+    }
+
+    /// Records that `sym` was coded.
+    #[inline]
+    pub fn update(&mut self, sym: u8) {
+        self.counts[sym as usize] += INCREMENT;
+        self.total += INCREMENT;
+        if self.total > COUNT_LIMIT {
+            // Rounding up keeps every count at least 1.
+            self.total = 0;
+            for c in &mut self.counts {
+                *c = c.div_ceil(2);
+                self.total += *c;
+            }
+        }
+        self.until_rebuild -= 1;
+        if self.until_rebuild == 0 {
+            self.table = FreqTable::from_counts(&self.counts).expect("counts are nonzero");
+            self.interval = (self.interval * 2).min(MAX_INTERVAL);
+            self.until_rebuild = self.interval;
+        }
+    }
+}
+
+/// Encodes `symbols` across `N` interleaved states with a fresh
+/// [`AdaptiveModel`]. `N = 1` gives a plain single-state stream.
+pub fn encode<const N: usize>(symbols: &[u8]) -> Vec<u8> {
+    // rANS codes in reverse, so replay the model forwards first and keep
+    // the slot each symbol was given.
+    let mut model = AdaptiveModel::new();
+    let slots: Vec<(u32, u32)> = symbols
+        .iter()
+        .map(|&s| {
+            let table = model.table();
+            let slot = (table.cum(s as usize), table.freq(s as usize));
+            model.update(s);
+            slot
+        })
+        .collect();
+
+    let mut enc = InterleavedEncoder::<N>::new();
+    for (i, &(start, freq)) in slots.iter().enumerate().rev() {
+        enc.put(i % N, start, freq);
+    }
+    enc.finish()
+}
+
+/// Decodes `len` symbols produced by [`encode`] with the same `N`, appending
+/// them to `out`. Fails with [`AnsxError::Corrupt`] unless the stream is
+/// consumed exactly.
+pub fn decode<const N: usize>(stream: &[u8], len: usize, out: &mut Vec<u8>) -> Result<()> {
+    let start = out.len();
+    out.resize(start + len, 0);
+    let result = decode_to_slice::<N>(stream, &mut out[start..]);
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result
+}
+
+/// [`decode`] of `out.len()` symbols straight into `out`.
+pub(crate) fn decode_to_slice<const N: usize>(stream: &[u8], out: &mut [u8]) -> Result<()> {
+    let mut dec = InterleavedDecoder::<N>::new(stream)?;
+    let mut model = AdaptiveModel::new();
+    for (i, o) in out.iter_mut().enumerate() {
+        let sym = dec.get_symbol(i % N, model.table())? as u8;
+        model.update(sym);
+        *o = sym;
+    }
+    if dec.is_finished() { Ok(()) } else { Err(AnsxError::Corrupt) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn model_learns_and_stays_normalized() {
+        let mut model = AdaptiveModel::new();
+        for _ in 0..10_000 {
+            model.update(b'x');
+        }
+        let table = model.table();
+        assert_eq!(table.freqs().iter().sum::<u32>(), crate::rans::PROB_SCALE);
+        assert!(table.freq(b'x' as usize) > 60_000);
+        assert!(table.freqs().iter().all(|&f| f >= 1));
+    }
+}
@@ -0,0 +1,91 @@
+//! Per-block compressibility estimates, computed without encoding.
+//!
+//! [`analyze`] splits the input into the same blocks as [`crate::encode`] and
+//! reports, for each, what the available models would cost in ideal bits
+//! (`Σ log₂(1 / p)`, before stream and header overhead), so a caller can pick
+//! a mode or study a dataset without running the coder.
+
+use crate::adaptive::AdaptiveModel;
+use crate::chunk::DEFAULT_BLOCK_SIZE;
+use crate::codec::Skip;
+use crate::gaussian::{BlockParams, GaussianTableBuilder};
+use crate::rans::{FreqTable, PROB_SCALE};
+use crate::zeta;
+
+/// Smallest σ fitted to a block, so constant blocks still get a valid model.
+pub const MIN_SIGMA: f32 = 0.5;
+
+/// Estimates for one block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockReport {
+    /// Offset of the block in the input.
+    pub offset: usize,
+    /// Block length in bytes.
+    pub len: usize,
+    /// Empirical order-0 entropy of the block, in bits.
+    pub entropy_bits: f64,
+    /// Size of the block's ζ-coded static table, in bits.
+    pub table_bits: u64,
+    /// (μ, σ) fitted to the byte values; σ is at least [`MIN_SIGMA`].
+    pub gaussian: BlockParams,
+    /// Cost under the Gaussian table built from [`BlockReport::gaussian`],
+    /// in bits.
+    pub gaussian_bits: f64,
+    /// Cost under a fresh [`AdaptiveModel`], in bits.
+    pub adaptive_bits: f64,
+    /// Skip the encoder would apply, if the block can be omitted.
+    pub skip: Option<Skip>,
+}
+
+impl BlockReport {
+    /// Approximate static-table cost: the entropy plus the table.
+    pub fn static_bits(&self) -> f64 {
+        self.entropy_bits + self.table_bits as f64
+    }
+}
+
+/// Analyzes `data` block by block.
+pub fn analyze(data: &[u8]) -> Vec<BlockReport> {
+    let block_size = DEFAULT_BLOCK_SIZE as usize;
+    let builder = GaussianTableBuilder::new();
+    let mut prev: Option<&[u8]> = None;
+    let mut reports = Vec::with_capacity(data.len().div_ceil(block_size));
+    for (i, block) in data.chunks(block_size).enumerate() {
+        let mut counts = [0u32; 256];
+        for &b in block {
+            counts[b as usize] += 1;
+        }
+        let n = block.len() as f64;
+        let used = || counts.iter().enumerate().filter(|&(_, &c)| c > 0).map(|(s, &c)| (s, c as f64));
+
+        let entropy_bits = used().map(|(_, c)| c * (n / c).log2()).sum();
+        let table = FreqTable::from_counts(&counts).expect("block is not empty");
+        let table_bits = zeta::encode_table(&table).len() as u64 * 8;
+
+        let mu = used().map(|(s, c)| s as f64 * c).sum::<f64>() / n;
+        let var = used().map(|(s, c)| (s as f64 - mu).powi(2) * c).sum::<f64>() / n;
+        let gaussian = BlockParams { mu: mu as f32, sigma: (var.sqrt() as f32).max(MIN_SIGMA) };
+        let gaussian_table = builder.build(gaussian).expect("fitted parameters are valid");
+        let gaussian_bits = used().map(|(s, c)| gaussian_table.cost_bits(s as u16) * c).sum();
+
+        let mut model = AdaptiveModel::new();
+        let mut adaptive_bits = 0.0;
+        for &b in block {
+            adaptive_bits += (PROB_SCALE as f64 / model.table().freq(b as usize) as f64).log2();
+            model.update(b);
+        }
+
+        reports.push(BlockReport {
+            offset: i * block_size,
+            len: block.len(),
+            entropy_bits,
+            table_bits,
+            gaussian,
+            gaussian_bits,
+            adaptive_bits,
+            skip: Skip::verify(block, prev),
+        });
+        prev = Some(block);
+    }
+    reports
+}
@@ -0,0 +1,143 @@
+//! Bit-level writer and reader.
+//!
+//! Bits are packed MSB-first, so a written bit string reads left to right in
+//! a hex dump. [`BitWriter::finish`] pads the last byte with zeros.
+
+use crate::error::{AnsxError, Result};
+
+/// Appends bit fields to a byte buffer.
+#[derive(Debug, Default)]
+pub struct BitWriter {
+    out: Vec<u8>,
+    acc: u64,
+    bits: u32,
+}
+
+impl BitWriter {
+    /// Creates an empty writer.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Writes the low `n` bits of `value`, most significant first (`n <= 32`).
+    pub fn write_bits(&mut self, value: u32, n: u32) {
+        debug_assert!(n <= 32 && (n == 32 || value >> n == 0));
+        if n == 0 {
+            return;
+        }
+        self.acc = (self.acc << n) | value as u64;
+        self.bits += n;
+        while self.bits >= 8 {
+            self.bits -= 8;
+            self.out.push((self.acc >> self.bits) as u8);
+        }
+    }
+
+    /// Writes one bit.
+    pub fn write_bit(&mut self, bit: bool) {
+        self.write_bits(bit as u32, 1);
+    }
+
+    /// Writes `value >= 1` as an Elias-gamma code: `k` zeros, then `value` in
+    /// `k + 1` bits.
+    pub fn write_gamma(&mut self, value: u32) {
+        debug_assert!(value >= 1);
+        let k = 31 - value.leading_zeros();
+        self.write_bits(0, k);
+        self.write_bits(value, k + 1);
+    }
+
+    /// Number of bits written so far.
+    pub fn bit_len(&self) -> usize {
+        self.out.len() * 8 + self.bits as usize
+    }
+
+    /// Pads to a byte boundary and returns the bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        if self.bits > 0 {
+            self.out.push((self.acc << (8 - self.bits)) as u8);
+        }
+        self.out
+    }
+}
+
+/// Reads bit fields written by [`BitWriter`].
+#[derive(Debug)]
+pub struct BitReader<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    acc: u64,
+    bits: u32,
+}
+
+impl<'a> BitReader<'a> {
+    /// Starts reading at the first bit of `buf`.
+    pub fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0, acc: 0, bits: 0 }
+    }
+
+    /// Reads `n` bits (`n <= 32`), most significant first.
+    pub fn read_bits(&mut self, n: u32) -> Result<u32> {
+        debug_assert!(n <= 32);
+        while self.bits < n {
+            let b = *self.buf.get(self.pos).ok_or(AnsxError::Truncated)?;
+            self.pos += 1;
+            self.acc = (self.acc << 8) | b as u64;
+            self.bits += 8;
+        }
+        self.bits -= n;
+        Ok(((self.acc >> self.bits) & ((1u64 << n) - 1)) as u32)
+    }
+
+    /// Reads one bit.
+    pub fn read_bit(&mut self) -> Result<bool> {
+        Ok(self.read_bits(1)? == 1)
+    }
+
+    /// Reads an Elias-gamma code written by [`BitWriter::write_gamma`].
+    pub fn read_gamma(&mut self) -> Result<u32> {
+        let mut k = 0;
+        while !self.read_bit()? {
+            k += 1;
+            if k > 31 {
+                return Err(AnsxError::BadHeader);
+            }
+        }
+        Ok((1 << k) | self.read_bits(k)?)
+    }
+
+    /// Bytes consumed, counting a partially read byte as consumed.
+    pub fn byte_pos(&self) -> usize {
+        self.pos - (self.bits / 8) as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fields_roundtrip() {
+        let mut w = BitWriter::new();
+        w.write_bit(true);
+        w.write_bits(0x5, 3);
+        w.write_bits(0xdead_beef, 32);
+        for v in [1, 2, 3, 255, 65_536, u32::MAX] {
+            w.write_gamma(v);
+        }
+        let bits = w.bit_len();
+        let bytes = w.finish();
+        assert_eq!(bytes.len(), bits.div_ceil(8));
+        assert_eq!(bytes[0] >> 4, 0b1101);
+
+        let mut r = BitReader::new(&bytes);
+        assert!(r.read_bit().unwrap());
+        assert_eq!(r.read_bits(3).unwrap(), 0x5);
+        assert_eq!(r.read_bits(32).unwrap(), 0xdead_beef);
+        for v in [1, 2, 3, 255, 65_536, u32::MAX] {
+            assert_eq!(r.read_gamma().unwrap(), v);
+        }
+        assert_eq!(r.byte_pos(), bytes.len());
+        assert_eq!(r.read_bits(8), Err(AnsxError::Truncated));
+    }
+}
@@ -0,0 +1,263 @@
+//! δ-tick bit-budget verification (docs/rbt_entropy_map.md, Axiom 1).
+//!
+//! The spec sets a block's budget to `Σ ceil(log₂ Δticks_i)` and requires the
+//! coder's output to match it within ±1 bit. Here `Δticks_i` is the number of
+//! ticks of the `PROB_SCALE` ring that one step of symbol `i` spans under the
+//! block's order-0 table, `ceil(PROB_SCALE / freq)`; a certain symbol costs
+//! 0 bits. Omitted blocks carry no information (Axiom 2), so their budget is 0.
+//!
+//! The budget is compared with the block's symbol stream, leaving out the
+//! chunk header, method byte, table and checksum, which the report lists
+//! separately as overhead. Verification is opt-in and only reads the encoded
+//! output, so it never changes what the encoder writes.
+
+use crate::chunk::{self, FLAG_PARITY};
+use crate::codec::{self, EncodeOptions};
+use crate::error::AnsxError;
+use crate::rans::{FreqTable, PROB_SCALE};
+use std::fmt;
+
+/// What [`verify`] does when a block misses its budget.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetPolicy {
+    /// Return the report with the offending blocks flagged.
+    #[default]
+    Warn,
+    /// Fail with [`BudgetError::OverBudget`].
+    Error,
+}
+
+/// Settings for [`verify`] and [`encode_verified`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BudgetOptions {
+    pub(crate) tolerance_bits: u64,
+    pub(crate) policy: BudgetPolicy,
+}
+
+impl Default for BudgetOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BudgetOptions {
+    /// Default settings: the spec's ±1 bit tolerance, violations only reported.
+    pub fn new() -> Self {
+        Self { tolerance_bits: 1, policy: BudgetPolicy::Warn }
+    }
+
+    /// Largest accepted difference between actual and budgeted bits.
+    pub fn tolerance_bits(mut self, bits: u64) -> Self {
+        self.tolerance_bits = bits;
+        self
+    }
+
+    /// Selects whether a violation is a warning or an error.
+    pub fn policy(mut self, policy: BudgetPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+}
+
+/// Budget check of one block.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockBudget {
+    /// Offset of the block in the input.
+    pub offset: usize,
+    /// Block length in bytes.
+    pub len: usize,
+    /// `true` if the block was omitted (zero or repeat skip).
+    pub skipped: bool,
+    /// `Σ ceil(log₂ Δticks_i)`.
+    pub budget_bits: u64,
+    /// `Σ log₂(PROB_SCALE / freq_i)` without rounding: the block's order-0
+    /// entropy under its quantized table.
+    pub entropy_bits: f64,
+    /// Size of the emitted symbol stream in bits.
+    pub actual_bits: u64,
+    /// Header, method byte, table and checksum bits around the stream.
+    pub overhead_bits: u64,
+}
+
+impl BlockBudget {
+    /// `actual_bits - budget_bits`; negative when the coder beat the budget.
+    pub fn deviation(&self) -> i64 {
+        self.actual_bits as i64 - self.budget_bits as i64
+    }
+
+    /// Returns `true` if the deviation is within `tolerance_bits` either way.
+    pub fn is_within(&self, tolerance_bits: u64) -> bool {
+        self.deviation().unsigned_abs() <= tolerance_bits
+    }
+}
+
+/// Per-block results of [`verify`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BudgetReport {
+    /// One entry per chunk, in stream order.
+    pub blocks: Vec<BlockBudget>,
+    /// Tolerance the blocks were checked against.
+    pub tolerance_bits: u64,
+}
+
+impl BudgetReport {
+    /// Blocks whose deviation exceeds the tolerance.
+    pub fn violations(&self) -> impl Iterator<Item = &BlockBudget> {
+        self.blocks.iter().filter(|b| !b.is_within(self.tolerance_bits))
+    }
+
+    /// Returns `true` if every block is within the tolerance.
+    pub fn is_within(&self) -> bool {
+        self.violations().next().is_none()
+    }
+
+    /// Sum of the block budgets.
+    pub fn budget_bits(&self) -> u64 {
+        self.blocks.iter().map(|b| b.budget_bits).sum()
+    }
+
+    /// Sum of the emitted symbol streams.
+    pub fn actual_bits(&self) -> u64 {
+        self.blocks.iter().map(|b| b.actual_bits).sum()
+    }
+
+    /// Sum of the per-block overhead.
+    pub fn overhead_bits(&self) -> u64 {
+        self.blocks.iter().map(|b| b.overhead_bits).sum()
+    }
+}
+
+impl fmt::Display for BudgetReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} blocks, budget {} bits, actual {} bits (+{} overhead), {} outside ±{} bits",
+            self.blocks.len(),
+            self.budget_bits(),
+            self.actual_bits(),
+            self.overhead_bits(),
+            self.violations().count(),
+            self.tolerance_bits,
+        )
+    }
+}
+
+/// Reasons [`verify`] fails.
+#[derive(Debug, Clone, PartialEq)]
+pub enum BudgetError {
+    /// The encoded stream is malformed or does not match the input length.
+    Stream(AnsxError),
+    /// Some block missed its budget under [`BudgetPolicy::Error`].
+    OverBudget(BudgetReport),
+}
+
+impl fmt::Display for BudgetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetError::Stream(e) => write!(f, "cannot check bit budget: {e}"),
+            BudgetError::OverBudget(report) => write!(f, "δ-tick bit budget exceeded: {report}"),
+        }
+    }
+}
+
+impl std::error::Error for BudgetError {}
+
+impl From<AnsxError> for BudgetError {
+    fn from(e: AnsxError) -> Self {
+        BudgetError::Stream(e)
+    }
+}
+
+/// `Δticks` of every symbol in `symbols` under `table`.
+///
+/// # Panics
+/// If a symbol has frequency 0 in `table`.
+pub fn delta_ticks(table: &FreqTable, symbols: &[u8]) -> Vec<u32> {
+    symbols
+        .iter()
+        .map(|&s| {
+            let f = table.freq(s as usize);
+            assert!(f > 0, "symbol {s} is not in the table");
+            PROB_SCALE.div_ceil(f)
+        })
+        .collect()
+}
+
+/// Budget and unrounded entropy of `block` under its own order-0 table.
+fn block_budget(block: &[u8]) -> (u64, f64) {
+    let mut counts = [0u32; 256];
+    for &b in block {
+        counts[b as usize] += 1;
+    }
+    let Some(table) = FreqTable::from_counts(&counts) else {
+        return (0, 0.0);
+    };
+    // Every occurrence of a byte has the same Δticks, so sum per symbol.
+    counts.iter().enumerate().filter(|&(_, &c)| c > 0).fold((0, 0.0), |(bits, entropy), (s, &c)| {
+        let f = table.freq(s);
+        let ceil_log2 = 32 - (PROB_SCALE.div_ceil(f) - 1).leading_zeros();
+        (bits + ceil_log2 as u64 * c as u64, entropy + (PROB_SCALE as f64 / f as f64).log2() * c as f64)
+    })
+}
+
+/// Checks each chunk of `encoded` against the δ-tick budget of the block of
+/// `data` it encodes.
+pub fn verify(data: &[u8], encoded: &[u8], options: &BudgetOptions) -> Result<BudgetReport, BudgetError> {
+    let mut blocks = Vec::new();
+    let mut offset = 0;
+    for chunk in chunk::chunks(encoded) {
+        let chunk = chunk.map_err(AnsxError::from)?;
+        let len = chunk.header.block_size as usize;
+        let block = data.get(offset..offset + len).ok_or(AnsxError::Corrupt)?;
+        let skipped = chunk.header.parity_flag & FLAG_PARITY != 0;
+        let (budget_bits, entropy_bits) = if skipped { (0, 0.0) } else { block_budget(block) };
+        let stream = codec::stream_len(&chunk)?;
+        blocks.push(BlockBudget {
+            offset,
+            len,
+            skipped,
+            budget_bits,
+            entropy_bits,
+            actual_bits: stream as u64 * 8,
+            overhead_bits: (chunk.encoded_len() - stream) as u64 * 8,
+        });
+        offset += len;
+    }
+    if offset != data.len() {
+        return Err(AnsxError::Corrupt.into());
+    }
+
+    let report = BudgetReport { blocks, tolerance_bits: options.tolerance_bits };
+    match options.policy {
+        BudgetPolicy::Error if !report.is_within() => Err(BudgetError::OverBudget(report)),
+        _ => Ok(report),
+    }
+}
+
+/// [`crate::encode_with`] followed by [`verify`] on the result.
+pub fn encode_verified(
+    data: &[u8],
+    options: &EncodeOptions,
+    budget: &BudgetOptions,
+) -> Result<(Vec<u8>, BudgetReport), BudgetError> {
+    let encoded = codec::encode_with(data, options);
+    let report = verify(data, &encoded, budget)?;
+    Ok((encoded, report))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn budget_rounds_each_symbol_up() {
+        // Frequencies 49152 and 16384: Δticks 2 and 4, i.e. 1 and 2 bits.
+        let block = [0, 0, 0, 1];
+        let (bits, entropy) = block_budget(&block);
+        assert_eq!(bits, 5);
+        assert!((entropy - (3.0 * (4.0f64 / 3.0).log2() + 2.0)).abs() < 1e-9);
+        assert_eq!(block_budget(&[7; 100]), (0, 0.0));
+        let table = FreqTable::from_counts(&[3, 1]).unwrap();
+        assert_eq!(delta_ticks(&table, &block), [2, 2, 2, 4]);
+    }
+}
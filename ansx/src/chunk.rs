@@ -0,0 +1,282 @@
+//! ANX1 chunk writer/reader (design doc §5.1).
+//!
+//! ```text
+//! chunk_id: "ANX1"     4 bytes
+//! chunk_len: u32 LE    4 bytes  bytes after this field (10 + cmpr_len)
+//! block_size: u16 LE   2 bytes  1..=MAX_BLOCK_SIZE
+//! cmpr_len: u32 LE     4 bytes
+//! parity_flag: u8      1 byte   bit 0: block omitted (parity skip)
+//!                               bit 1: omitted block repeats the previous one
+//!                               bit 2: payload uses the adaptive model
+//!                               bit 3: payload ends with a CRC-32 trailer
+//! lanes: u8            1 byte   interleaved rANS states: 0 (single), 4 or 8
+//! backend: u8          1 byte   entropy coder: 0 = rANS, 1 = tANS
+//! reserved: u8         1 byte   must be zero
+//! compressed_data      cmpr_len bytes
+//! ```
+//!
+//! Parsing is strict: unknown flag bits, nonzero reserved bytes and
+//! inconsistent lengths are rejected rather than ignored.
+
+use std::fmt;
+
+/// Chunk identifier.
+pub const CHUNK_ID: [u8; 4] = *b"ANX1";
+/// Size of the fixed chunk header including id and length.
+pub const HEADER_LEN: usize = 18;
+/// Typical block size.
+pub const DEFAULT_BLOCK_SIZE: u16 = 16384;
+/// Largest accepted block size.
+pub const MAX_BLOCK_SIZE: u16 = 32768;
+/// `parity_flag` bit 0: parity optimization applied, payload omitted.
+pub const FLAG_PARITY: u8 = 0x01;
+/// `parity_flag` bit 1: the omitted block repeats the previous block
+/// instead of being zeros. Only valid together with [`FLAG_PARITY`].
+pub const FLAG_REPEAT: u8 = 0x02;
+/// `parity_flag` bit 2: the rANS payload is coded with the adaptive order-0
+/// model and carries no frequency table. Invalid on omitted blocks.
+pub const FLAG_ADAPTIVE: u8 = 0x04;
+/// `parity_flag` bit 3: the payload ends with the CRC-32 (u32 LE) of the
+/// decoded block. Invalid on omitted blocks.
+pub const FLAG_CHECKSUM: u8 = 0x08;
+
+/// Accepted values of the `lanes` byte; 0 means a single rANS state.
+pub const LANE_COUNTS: [u8; 3] = [0, 4, 8];
+
+/// `backend` byte: the payload is coded with rANS (see [`crate::rans`]).
+pub const BACKEND_RANS: u8 = 0;
+/// `backend` byte: the payload is coded with tANS (see [`crate::tans`]).
+/// Only valid on single-lane blocks with a static table.
+pub const BACKEND_TANS: u8 = 1;
+
+const KNOWN_FLAGS: u8 = FLAG_PARITY | FLAG_REPEAT | FLAG_ADAPTIVE | FLAG_CHECKSUM;
+// Flags describing a payload, meaningless on omitted blocks.
+const PAYLOAD_FLAGS: u8 = FLAG_ADAPTIVE | FLAG_CHECKSUM;
+// Header bytes counted by `chunk_len` besides the payload.
+const LEN_FIELDS: u32 = (HEADER_LEN - 8) as u32;
+
+/// Reasons an ANX1 chunk is rejected.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChunkError {
+    /// Input ends inside the header or payload.
+    Truncated,
+    /// Chunk id is not `"ANX1"`.
+    BadId,
+    /// `chunk_len` disagrees with `cmpr_len`, or the payload exceeds `u32`.
+    LengthMismatch,
+    /// `block_size` is zero or above [`MAX_BLOCK_SIZE`].
+    BlockSize(u16),
+    /// Undefined `parity_flag` bits are set.
+    UnknownFlags(u8),
+    /// Flag bits contradict each other or the payload length.
+    InvalidFlags(u8),
+    /// `lanes` is not one of [`LANE_COUNTS`].
+    UnknownLanes(u8),
+    /// `backend` is not a known entropy coder.
+    UnknownBackend(u8),
+    /// `backend` contradicts the flags or the lane count.
+    InvalidBackend(u8),
+    /// A reserved byte is nonzero.
+    ReservedNonZero,
+}
+
+impl fmt::Display for ChunkError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ChunkError::Truncated => f.write_str("ANX1 chunk is truncated"),
+            ChunkError::BadId => f.write_str("chunk id is not ANX1"),
+            ChunkError::LengthMismatch => f.write_str("ANX1 chunk_len does not match cmpr_len"),
+            ChunkError::BlockSize(n) => write!(f, "ANX1 block_size {n} out of range"),
+            ChunkError::UnknownFlags(b) => write!(f, "ANX1 parity_flag has unknown bits {b:#04x}"),
+            ChunkError::InvalidFlags(b) => write!(f, "ANX1 parity_flag {b:#04x} is inconsistent"),
+            ChunkError::UnknownLanes(n) => write!(f, "ANX1 lane count {n} is not supported"),
+            ChunkError::UnknownBackend(b) => write!(f, "ANX1 backend {b} is not supported"),
+            ChunkError::InvalidBackend(b) => write!(f, "ANX1 backend {b} does not fit the flags or lanes"),
+            ChunkError::ReservedNonZero => f.write_str("ANX1 reserved byte is nonzero"),
+        }
+    }
+}
+
+impl std::error::Error for ChunkError {}
+
+impl From<ChunkError> for crate::AnsxError {
+    fn from(e: ChunkError) -> Self {
+        match e {
+            ChunkError::Truncated => crate::AnsxError::Truncated,
+            _ => crate::AnsxError::BadHeader,
+        }
+    }
+}
+
+/// Fixed-size ANX1 header fields.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ChunkHeader {
+    /// Block size in bytes.
+    pub block_size: u16,
+    /// Length of the compressed payload.
+    pub cmpr_len: u32,
+    /// Flag bits, see [`FLAG_PARITY`].
+    pub parity_flag: u8,
+    /// Interleaved rANS states; 0 for a single state.
+    pub lanes: u8,
+    /// Entropy coder of the payload, see [`BACKEND_RANS`].
+    pub backend: u8,
+}
+
+impl ChunkHeader {
+    /// Parses and validates the fixed header at the front of `input`; the
+    /// payload does not need to be present.
+    pub fn parse(input: &[u8]) -> Result<Self, ChunkError> {
+        let head = input.get(..HEADER_LEN).ok_or(ChunkError::Truncated)?;
+        if head[..4] != CHUNK_ID {
+            return Err(ChunkError::BadId);
+        }
+        let u32_at = |i: usize| u32::from_le_bytes(head[i..i + 4].try_into().unwrap());
+        let chunk_len = u32_at(4);
+        let header = ChunkHeader {
+            block_size: u16::from_le_bytes([head[8], head[9]]),
+            cmpr_len: u32_at(10),
+            parity_flag: head[14],
+            lanes: head[15],
+            backend: head[16],
+        };
+        if head[17] != 0 {
+            return Err(ChunkError::ReservedNonZero);
+        }
+        header.validate()?;
+        if chunk_len != LEN_FIELDS + header.cmpr_len {
+            return Err(ChunkError::LengthMismatch);
+        }
+        Ok(header)
+    }
+
+    fn validate(&self) -> Result<(), ChunkError> {
+        if self.block_size == 0 || self.block_size > MAX_BLOCK_SIZE {
+            return Err(ChunkError::BlockSize(self.block_size));
+        }
+        if self.parity_flag & !KNOWN_FLAGS != 0 {
+            return Err(ChunkError::UnknownFlags(self.parity_flag));
+        }
+        let skipped = self.parity_flag & FLAG_PARITY != 0;
+        if (self.parity_flag & FLAG_REPEAT != 0 && !skipped)
+            || (skipped && (self.cmpr_len != 0 || self.parity_flag & PAYLOAD_FLAGS != 0))
+        {
+            return Err(ChunkError::InvalidFlags(self.parity_flag));
+        }
+        if !LANE_COUNTS.contains(&self.lanes) {
+            return Err(ChunkError::UnknownLanes(self.lanes));
+        }
+        match self.backend {
+            BACKEND_RANS => {}
+            BACKEND_TANS if !skipped && self.lanes == 0 && self.parity_flag & FLAG_ADAPTIVE == 0 => {}
+            BACKEND_TANS => return Err(ChunkError::InvalidBackend(self.backend)),
+            b => return Err(ChunkError::UnknownBackend(b)),
+        }
+        if self.cmpr_len > u32::MAX - LEN_FIELDS {
+            return Err(ChunkError::LengthMismatch);
+        }
+        Ok(())
+    }
+}
+
+/// A parsed or to-be-written chunk borrowing its payload.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Chunk<'a> {
+    /// Header fields.
+    pub header: ChunkHeader,
+    /// Compressed payload (`header.cmpr_len` bytes).
+    pub data: &'a [u8],
+}
+
+impl<'a> Chunk<'a> {
+    /// Builds a chunk around `data`, validating the header fields.
+    pub fn new(block_size: u16, parity_flag: u8, data: &'a [u8]) -> Result<Self, ChunkError> {
+        let cmpr_len = u32::try_from(data.len()).map_err(|_| ChunkError::LengthMismatch)?;
+        let header = ChunkHeader { block_size, cmpr_len, parity_flag, lanes: 0, backend: BACKEND_RANS };
+        header.validate()?;
+        Ok(Self { header, data })
+    }
+
+    /// Sets the interleaved lane count, validating it.
+    pub fn with_lanes(mut self, lanes: u8) -> Result<Self, ChunkError> {
+        self.header.lanes = lanes;
+        self.header.validate()?;
+        Ok(self)
+    }
+
+    /// Sets the entropy coder byte, validating it.
+    pub fn with_backend(mut self, backend: u8) -> Result<Self, ChunkError> {
+        self.header.backend = backend;
+        self.header.validate()?;
+        Ok(self)
+    }
+
+    /// Total serialized size.
+    pub fn encoded_len(&self) -> usize {
+        HEADER_LEN + self.data.len()
+    }
+
+    /// Appends the serialized chunk to `out`.
+    pub fn write(&self, out: &mut Vec<u8>) {
+        let start = out.len();
+        out.resize(start + self.encoded_len(), 0);
+        self.write_to(&mut out[start..]);
+    }
+
+    /// Serializes the chunk into the front of `out`.
+    ///
+    /// # Panics
+    /// If `out` is shorter than [`Chunk::encoded_len`].
+    pub fn write_to(&self, out: &mut [u8]) {
+        let h = &self.header;
+        let (head, data) = out[..self.encoded_len()].split_at_mut(HEADER_LEN);
+        head[..4].copy_from_slice(&CHUNK_ID);
+        head[4..8].copy_from_slice(&(LEN_FIELDS + h.cmpr_len).to_le_bytes());
+        head[8..10].copy_from_slice(&h.block_size.to_le_bytes());
+        head[10..14].copy_from_slice(&h.cmpr_len.to_le_bytes());
+        head[14..].copy_from_slice(&[h.parity_flag, h.lanes, h.backend, 0]);
+        data.copy_from_slice(self.data);
+    }
+
+    /// Parses one chunk from the front of `input`, returning it and the rest.
+    pub fn parse(input: &'a [u8]) -> Result<(Self, &'a [u8]), ChunkError> {
+        let header = ChunkHeader::parse(input)?;
+        // On 32-bit targets a payload near `u32::MAX` cannot fit in the input.
+        let end = usize::try_from(header.cmpr_len)
+            .ok()
+            .and_then(|len| HEADER_LEN.checked_add(len))
+            .ok_or(ChunkError::Truncated)?;
+        let data = input.get(HEADER_LEN..end).ok_or(ChunkError::Truncated)?;
+        Ok((Self { header, data }, &input[end..]))
+    }
+}
+
+/// Iterates over back-to-back chunks; stops after the first error.
+pub fn chunks(input: &[u8]) -> Chunks<'_> {
+    Chunks { rest: input }
+}
+
+/// Iterator returned by [`chunks`].
+pub struct Chunks<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Iterator for Chunks<'a> {
+    type Item = Result<Chunk<'a>, ChunkError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.rest.is_empty() {
+            return None;
+        }
+        match Chunk::parse(self.rest) {
+            Ok((chunk, rest)) => {
+                self.rest = rest;
+                Some(Ok(chunk))
+            }
+            Err(e) => {
+                self.rest = &[];
+                Some(Err(e))
+            }
+        }
+    }
+}
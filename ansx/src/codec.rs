@@ -0,0 +1,550 @@
+//! Order-0 byte codec and its stream format.
+//!
+//! A stream is a sequence of ANX1 chunks (see [`crate::chunk`]), one per block
+//! of up to [`DEFAULT_BLOCK_SIZE`] input bytes. `block_size` holds the block's
+//! decoded length. The payload of a coded block is:
+//!
+//! ```text
+//! method: u8   0 = stored, 1 = entropy-coded order-0
+//! stored:      block_size bytes
+//! rANS:        ζ-coded 256-symbol table padded to a byte (see [`crate::zeta`]), rANS stream
+//! tANS:        tANS table padded to a byte, tANS stream (see [`crate::tans`])
+//! ```
+//!
+//! The chunk's `backend` byte selects rANS or tANS for coded payloads. The
+//! stored method is used whenever coding would not be smaller. When the chunk's
+//! `lanes` byte is 4 or 8 the rANS stream is interleaved across that many
+//! states (see [`crate::rans::InterleavedEncoder`]); 0 means a single state.
+//!
+//! With [`FLAG_ADAPTIVE`] set, the rANS payload has no table: the method byte is
+//! followed directly by a stream coded with [`crate::adaptive::AdaptiveModel`].
+//! With [`FLAG_CHECKSUM`] set, the payload ends with the CRC-32 of the decoded
+//! block (u32 LE), checked after the block is rebuilt.
+//!
+//! # Parity skipping
+//!
+//! The design docs omit "even-parity" blocks and decode them as zeros, which
+//! loses data for any such block that is not actually zero. Here a block is
+//! omitted (`FLAG_PARITY` set, empty payload) only after the encoder has
+//! checked that the decoder can rebuild it exactly: either every byte is zero,
+//! or it equals the previous block (`FLAG_REPEAT`). Parity alone never causes
+//! a skip.
+
+use crate::adaptive;
+use crate::checksum::crc32;
+use crate::chunk::{
+    self, BACKEND_RANS, BACKEND_TANS, Chunk, ChunkHeader, DEFAULT_BLOCK_SIZE, FLAG_ADAPTIVE, FLAG_CHECKSUM, FLAG_PARITY,
+    FLAG_REPEAT, HEADER_LEN,
+};
+use crate::error::{AnsxError, Result};
+use crate::rans::{FreqTable, InterleavedDecoder, InterleavedEncoder, RansDecoder, RansEncoder};
+use crate::simd::Kernel;
+use crate::tans::{self, TansTable};
+use crate::zeta;
+
+const METHOD_STORED: u8 = 0;
+const METHOD_CODED: u8 = 1;
+
+/// Number of rANS states a block's stream is interleaved across.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interleave {
+    /// One state; the original Phase A stream.
+    #[default]
+    Single,
+    /// Four interleaved states.
+    Four,
+    /// Eight interleaved states.
+    Eight,
+}
+
+impl Interleave {
+    /// Value of the ANX1 `lanes` byte.
+    pub fn lanes(self) -> u8 {
+        match self {
+            Interleave::Single => 0,
+            Interleave::Four => 4,
+            Interleave::Eight => 8,
+        }
+    }
+
+    fn from_lanes(lanes: u8) -> Result<Self> {
+        match lanes {
+            0 => Ok(Interleave::Single),
+            4 => Ok(Interleave::Four),
+            8 => Ok(Interleave::Eight),
+            _ => Err(AnsxError::BadHeader),
+        }
+    }
+}
+
+/// Probability model for coded blocks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Model {
+    /// Per-block table computed from the block and stored in its payload.
+    #[default]
+    Static,
+    /// Online-updating order-0 model; no table is stored.
+    Adaptive,
+}
+
+/// Entropy coder for blocks with a static table.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Backend {
+    /// Range ANS; supports interleaving.
+    #[default]
+    Rans,
+    /// Table ANS (FSE-style), single state only. Decoding needs no division.
+    Tans,
+}
+
+impl Backend {
+    /// Value of the ANX1 `backend` byte.
+    pub fn id(self) -> u8 {
+        match self {
+            Backend::Rans => BACKEND_RANS,
+            Backend::Tans => BACKEND_TANS,
+        }
+    }
+}
+
+/// Encoder settings for [`encode_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct EncodeOptions {
+    pub(crate) interleave: Interleave,
+    pub(crate) model: Model,
+    pub(crate) backend: Backend,
+    pub(crate) checksum: bool,
+}
+
+impl Default for EncodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl EncodeOptions {
+    /// Default settings: single-state rANS with a static table and a CRC-32
+    /// per coded block.
+    pub fn new() -> Self {
+        Self { interleave: Interleave::Single, model: Model::Static, backend: Backend::Rans, checksum: true }
+    }
+
+    /// Selects the interleaved stream variant. Ignored by [`Backend::Tans`].
+    pub fn interleave(mut self, interleave: Interleave) -> Self {
+        self.interleave = interleave;
+        self
+    }
+
+    /// Selects the probability model.
+    pub fn model(mut self, model: Model) -> Self {
+        self.model = model;
+        self
+    }
+
+    /// Selects the entropy coder for static-table blocks. Adaptive blocks
+    /// always use rANS.
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    /// Enables or disables the CRC-32 trailer on coded blocks (4 bytes each).
+    pub fn checksum(mut self, enabled: bool) -> Self {
+        self.checksum = enabled;
+        self
+    }
+}
+
+/// Decoder settings for [`decode_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DecodeOptions {
+    pub(crate) kernel: Kernel,
+    pub(crate) max_output: usize,
+}
+
+impl Default for DecodeOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DecodeOptions {
+    /// Default settings: automatic kernel selection, no output limit.
+    pub fn new() -> Self {
+        Self { kernel: Kernel::Auto, max_output: usize::MAX }
+    }
+
+    /// Fails with [`AnsxError::OutputTooLarge`] instead of producing more
+    /// than `limit` bytes. Checked against the chunk headers before decoding.
+    pub fn max_output(mut self, limit: usize) -> Self {
+        self.max_output = limit;
+        self
+    }
+
+    /// Forces a decode kernel. The decoded bytes never depend on this.
+    pub fn kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+}
+
+/// How an omitted block is rebuilt by the decoder.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Skip {
+    /// Block is all zeros.
+    Zeros,
+    /// Block equals the previous block.
+    Repeat,
+}
+
+impl Skip {
+    /// Returns the skip that reproduces `block` exactly, if any.
+    ///
+    /// This is the encoder's verification step: a flag is only ever set when
+    /// the reconstruction has been compared byte for byte.
+    pub fn verify(block: &[u8], prev: Option<&[u8]>) -> Option<Skip> {
+        if block.iter().all(|&b| b == 0) {
+            Some(Skip::Zeros)
+        } else if prev == Some(block) {
+            Some(Skip::Repeat)
+        } else {
+            None
+        }
+    }
+
+    fn flags(self) -> u8 {
+        match self {
+            Skip::Zeros => FLAG_PARITY,
+            Skip::Repeat => FLAG_PARITY | FLAG_REPEAT,
+        }
+    }
+}
+
+/// Backend actually used for coded blocks under `options`.
+fn effective_backend(options: &EncodeOptions) -> Backend {
+    if options.model == Model::Adaptive { Backend::Rans } else { options.backend }
+}
+
+/// Writes the smaller of the coded and stored payloads for `block` into
+/// `payload`, replacing its contents.
+fn encode_payload(block: &[u8], options: &EncodeOptions, payload: &mut Vec<u8>) {
+    payload.clear();
+    payload.push(METHOD_CODED);
+    if options.model == Model::Adaptive {
+        payload.extend_from_slice(&match options.interleave {
+            Interleave::Single => adaptive::encode::<1>(block),
+            Interleave::Four => adaptive::encode::<4>(block),
+            Interleave::Eight => adaptive::encode::<8>(block),
+        });
+    } else if options.backend == Backend::Tans {
+        let mut counts = [0u32; 256];
+        for &b in block {
+            counts[b as usize] += 1;
+        }
+        if let Some(table) = TansTable::from_counts(&counts, tans::TABLE_LOG) {
+            payload.extend_from_slice(&tans::encode_table(&table));
+            payload.extend_from_slice(&tans::encode(&table, block));
+        }
+    } else {
+        let mut counts = [0u32; 256];
+        for &b in block {
+            counts[b as usize] += 1;
+        }
+        if let Some(table) = FreqTable::from_counts(&counts) {
+            payload.extend_from_slice(&zeta::encode_table(&table));
+            payload.extend_from_slice(&match options.interleave {
+                Interleave::Single => {
+                    let mut enc = RansEncoder::new();
+                    for &b in block.iter().rev() {
+                        enc.put_symbol(&table, b as usize);
+                    }
+                    enc.finish()
+                }
+                Interleave::Four => InterleavedEncoder::<4>::new().encode(&table, block),
+                Interleave::Eight => InterleavedEncoder::<8>::new().encode(&table, block),
+            });
+        }
+    }
+
+    // Store the block if no table was built or coding is not smaller than stored.
+    if payload.len() == 1 || payload.len() > block.len() {
+        payload.clear();
+        payload.push(METHOD_STORED);
+        payload.extend_from_slice(block);
+    }
+}
+
+/// Length of the symbol stream in `chunk`'s payload: what is left after the
+/// method byte, any frequency table and any CRC-32 trailer. Stored blocks
+/// count their raw bytes; omitted blocks have none.
+pub(crate) fn stream_len(chunk: &Chunk<'_>) -> Result<usize> {
+    let flags = chunk.header.parity_flag;
+    if flags & FLAG_PARITY != 0 {
+        return Ok(0);
+    }
+    let trailer = if flags & FLAG_CHECKSUM != 0 { 4 } else { 0 };
+    let payload = chunk.data.len().checked_sub(trailer).map(|n| &chunk.data[..n]).ok_or(AnsxError::Truncated)?;
+    let (&method, body) = payload.split_first().ok_or(AnsxError::Truncated)?;
+    let table_len = match method {
+        METHOD_STORED => 0,
+        METHOD_CODED if flags & FLAG_ADAPTIVE != 0 => 0,
+        METHOD_CODED if chunk.header.backend == BACKEND_TANS => tans::decode_table(body)?.1,
+        METHOD_CODED => zeta::decode_table(body)?.1,
+        _ => return Err(AnsxError::BadHeader),
+    };
+    Ok(body.len() - table_len)
+}
+
+/// Decodes `payload` into `out`, which holds exactly one block.
+fn decode_payload(header: &ChunkHeader, payload: &[u8], kernel: Kernel, out: &mut [u8]) -> Result<()> {
+    let len = out.len();
+    let interleave = Interleave::from_lanes(header.lanes)?;
+    let (&method, body) = payload.split_first().ok_or(AnsxError::Truncated)?;
+    match method {
+        METHOD_STORED => match body.len().cmp(&len) {
+            std::cmp::Ordering::Less => Err(AnsxError::Truncated),
+            std::cmp::Ordering::Greater => Err(AnsxError::Corrupt),
+            std::cmp::Ordering::Equal => {
+                out.copy_from_slice(body);
+                Ok(())
+            }
+        },
+        METHOD_CODED if header.backend == BACKEND_TANS => {
+            let (table, table_len) = tans::decode_table(body)?;
+            tans::decode_to_slice(&table, &body[table_len..], out)
+        }
+        METHOD_CODED if header.parity_flag & FLAG_ADAPTIVE != 0 => match interleave {
+            Interleave::Single => adaptive::decode_to_slice::<1>(body, out),
+            Interleave::Four => adaptive::decode_to_slice::<4>(body, out),
+            Interleave::Eight => adaptive::decode_to_slice::<8>(body, out),
+        },
+        METHOD_CODED => {
+            let (table, table_len) = zeta::decode_table(body)?;
+            if table.len() != 256 {
+                return Err(AnsxError::BadHeader);
+            }
+
+            let stream = &body[table_len..];
+            let finished = match interleave {
+                Interleave::Single => {
+                    let mut dec = RansDecoder::new(stream)?;
+                    for o in out {
+                        *o = dec.get_symbol(&table)? as u8;
+                    }
+                    dec.is_finished()
+                }
+                Interleave::Four => {
+                    let mut dec = InterleavedDecoder::<4>::new(stream)?;
+                    dec.decode_to_slice(&table, out, kernel)?;
+                    dec.is_finished()
+                }
+                Interleave::Eight => {
+                    let mut dec = InterleavedDecoder::<8>::new(stream)?;
+                    dec.decode_to_slice(&table, out, kernel)?;
+                    dec.is_finished()
+                }
+            };
+            if finished { Ok(()) } else { Err(AnsxError::Corrupt) }
+        }
+        _ => Err(AnsxError::BadHeader),
+    }
+}
+
+/// Compresses `data` into ANX1 chunks of order-0 rANS blocks with static tables.
+pub fn encode(data: &[u8]) -> Vec<u8> {
+    encode_with(data, &EncodeOptions::default())
+}
+
+/// [`encode`] with explicit settings.
+pub fn encode_with(data: &[u8], options: &EncodeOptions) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_into_with(data, &mut out, options);
+    out
+}
+
+/// [`encode`], appending to `out` so its allocation can be reused across calls.
+pub fn encode_into(data: &[u8], out: &mut Vec<u8>) {
+    encode_into_with(data, out, &EncodeOptions::default())
+}
+
+/// [`encode_into`] with explicit settings.
+pub fn encode_into_with(data: &[u8], out: &mut Vec<u8>, options: &EncodeOptions) {
+    out.reserve(max_encoded_len(data.len()));
+    let mut payload = Vec::new();
+    let mut prev: Option<&[u8]> = None;
+    for block in data.chunks(DEFAULT_BLOCK_SIZE as usize) {
+        encode_block(block, prev, options, &mut payload, out);
+        prev = Some(block);
+    }
+}
+
+/// [`encode_with`] straight into `out`, returning the encoded size.
+///
+/// Chunks are written while they fit, so a result above `out.len()` means
+/// `out` only holds a prefix; [`max_encoded_len`] bytes always suffice.
+pub(crate) fn encode_to_slice(data: &[u8], out: &mut [u8], options: &EncodeOptions) -> usize {
+    let mut payload = Vec::new();
+    let mut prev: Option<&[u8]> = None;
+    let mut pos = 0;
+    for block in data.chunks(DEFAULT_BLOCK_SIZE as usize) {
+        let chunk = block_chunk(block, prev, options, &mut payload);
+        let end = pos + chunk.encoded_len();
+        if let Some(dst) = out.get_mut(pos..end) {
+            chunk.write_to(dst);
+        }
+        pos = end;
+        prev = Some(block);
+    }
+    pos
+}
+
+/// Appends the chunk for one block (at most [`chunk::MAX_BLOCK_SIZE`] bytes)
+/// that follows `prev`, using `payload` as scratch space.
+pub(crate) fn encode_block(
+    block: &[u8],
+    prev: Option<&[u8]>,
+    options: &EncodeOptions,
+    payload: &mut Vec<u8>,
+    out: &mut Vec<u8>,
+) {
+    block_chunk(block, prev, options, payload).write(out);
+}
+
+/// Builds the chunk for `block`, coding its payload into `payload`.
+fn block_chunk<'a>(block: &[u8], prev: Option<&[u8]>, options: &EncodeOptions, payload: &'a mut Vec<u8>) -> Chunk<'a> {
+    let (flags, lanes, backend) = match Skip::verify(block, prev) {
+        Some(skip) => {
+            payload.clear();
+            (skip.flags(), 0, Backend::Rans)
+        }
+        None => {
+            encode_payload(block, options, payload);
+            let coded = payload[0] == METHOD_CODED;
+            let mut flags = 0;
+            if coded && options.model == Model::Adaptive {
+                flags |= FLAG_ADAPTIVE;
+            }
+            if options.checksum {
+                payload.extend_from_slice(&crc32(block).to_le_bytes());
+                flags |= FLAG_CHECKSUM;
+            }
+            match effective_backend(options) {
+                Backend::Rans if coded => (flags, options.interleave.lanes(), Backend::Rans),
+                Backend::Tans if coded => (flags, 0, Backend::Tans),
+                _ => (flags, 0, Backend::Rans),
+            }
+        }
+    };
+    Chunk::new(block.len() as u16, flags, payload)
+        .and_then(|c| c.with_lanes(lanes))
+        .and_then(|c| c.with_backend(backend.id()))
+        .expect("block header is always valid")
+}
+
+/// Upper bound on the encoded size of `len` input bytes under any
+/// [`EncodeOptions`]: every block at worst costs a chunk header, the method
+/// byte and a CRC-32 on top of its stored bytes. Saturates at `usize::MAX`.
+pub fn max_encoded_len(len: usize) -> usize {
+    len.saturating_add(len.div_ceil(DEFAULT_BLOCK_SIZE as usize) * (HEADER_LEN + 1 + 4))
+}
+
+/// Decompresses a stream produced by [`encode`].
+pub fn decode(input: &[u8]) -> Result<Vec<u8>> {
+    decode_with(input, &DecodeOptions::default())
+}
+
+/// [`decode`] with explicit settings.
+pub fn decode_with(input: &[u8], options: &DecodeOptions) -> Result<Vec<u8>> {
+    let mut out = Vec::new();
+    decode_into_with(input, &mut out, options)?;
+    Ok(out)
+}
+
+/// [`decode`], appending to `out`. On error `out` is restored to its
+/// original length.
+pub fn decode_into(input: &[u8], out: &mut Vec<u8>) -> Result<()> {
+    decode_into_with(input, out, &DecodeOptions::default())
+}
+
+/// [`decode_into`] with explicit settings; the output limit applies to the
+/// bytes appended.
+pub fn decode_into_with(input: &[u8], out: &mut Vec<u8>, options: &DecodeOptions) -> Result<()> {
+    let total = decoded_len(input)?;
+    if total > options.max_output {
+        return Err(AnsxError::OutputTooLarge);
+    }
+    let base = out.len();
+    out.resize(base + total, 0);
+    let result = decode_to_slice(input, &mut out[base..], options.kernel);
+    if result.is_err() {
+        out.truncate(base);
+    }
+    result
+}
+
+/// Validates every chunk header and sums the decoded block sizes, without
+/// decoding any payload.
+pub fn decoded_len(input: &[u8]) -> Result<usize> {
+    let mut total: usize = 0;
+    for chunk in chunk::chunks(input) {
+        total = total.saturating_add(chunk?.header.block_size as usize);
+    }
+    Ok(total)
+}
+
+/// Decodes `input` straight into `out`, which must be exactly
+/// [`decoded_len`] bytes long.
+pub(crate) fn decode_to_slice(input: &[u8], out: &mut [u8], kernel: Kernel) -> Result<()> {
+    let mut prev: Option<usize> = None;
+    let mut start = 0;
+    for chunk in chunk::chunks(input) {
+        let chunk = chunk?;
+        let (done, rest) = out.split_at_mut(start);
+        let dst = rest.get_mut(..chunk.header.block_size as usize).ok_or(AnsxError::OutputTooLarge)?;
+        decode_block_to(&chunk, prev.map(|p| &done[p..]), kernel, dst)?;
+        prev = Some(start);
+        start += dst.len();
+    }
+    if start == out.len() { Ok(()) } else { Err(AnsxError::Truncated) }
+}
+
+/// Appends the block rebuilt from `chunk` to `out`; `prev` is the range of
+/// `out` holding the previous block, if any.
+pub(crate) fn decode_block(
+    chunk: &Chunk<'_>,
+    prev: Option<std::ops::Range<usize>>,
+    kernel: Kernel,
+    out: &mut Vec<u8>,
+) -> Result<()> {
+    let start = out.len();
+    out.resize(start + chunk.header.block_size as usize, 0);
+    let (done, dst) = out.split_at_mut(start);
+    let result = decode_block_to(chunk, prev.and_then(|r| done.get(r)), kernel, dst);
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result
+}
+
+/// Rebuilds the block `chunk` encodes in `out`, which must be exactly
+/// `block_size` bytes; `prev` is the previous block, if any.
+pub(crate) fn decode_block_to(chunk: &Chunk<'_>, prev: Option<&[u8]>, kernel: Kernel, out: &mut [u8]) -> Result<()> {
+    debug_assert_eq!(out.len(), chunk.header.block_size as usize);
+    let flags = chunk.header.parity_flag;
+    if flags & FLAG_REPEAT != 0 {
+        let prev = prev.filter(|p| p.len() == out.len()).ok_or(AnsxError::Corrupt)?;
+        out.copy_from_slice(prev);
+    } else if flags & FLAG_PARITY != 0 {
+        out.fill(0);
+    } else if flags & FLAG_CHECKSUM != 0 {
+        let split = chunk.data.len().checked_sub(4).ok_or(AnsxError::Truncated)?;
+        let (body, trailer) = chunk.data.split_at(split);
+        decode_payload(&chunk.header, body, kernel, out)?;
+        if crc32(out).to_le_bytes() != trailer {
+            return Err(AnsxError::Checksum);
+        }
+    } else {
+        decode_payload(&chunk.header, chunk.data, kernel, out)?;
+    }
+    Ok(())
+}
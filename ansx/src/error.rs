@@ -0,0 +1,48 @@
+//! Error type shared by the ANS-X encoder and decoder.
+
+use std::fmt;
+
+/// Reasons an ANS-X stream can fail to decode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AnsxError {
+    /// Input ended before the stream was complete.
+    Truncated,
+    /// Unknown method byte or malformed frequency table.
+    BadHeader,
+    /// Stream is well-formed but does not decode consistently.
+    Corrupt,
+    /// Model parameters are invalid or do not match the input.
+    InvalidModel,
+    /// A block decoded to bytes that fail its CRC-32.
+    Checksum,
+    /// Decoded output would exceed the configured limit.
+    OutputTooLarge,
+}
+
+/// Result type for ANS-X operations.
+pub type Result<T> = std::result::Result<T, AnsxError>;
+
+impl fmt::Display for AnsxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            AnsxError::Truncated => "ansx stream is truncated",
+            AnsxError::BadHeader => "ansx stream has a malformed header",
+            AnsxError::Corrupt => "ansx stream is corrupt",
+            AnsxError::InvalidModel => "ansx model parameters are invalid",
+            AnsxError::Checksum => "ansx block checksum mismatch",
+            AnsxError::OutputTooLarge => "ansx output exceeds the size limit",
+        })
+    }
+}
+
+impl std::error::Error for AnsxError {}
+
+impl From<AnsxError> for std::io::Error {
+    fn from(e: AnsxError) -> Self {
+        let kind = match e {
+            AnsxError::Truncated => std::io::ErrorKind::UnexpectedEof,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
@@ -0,0 +1,710 @@
+//! C ABI shims over the safe [`crate::encode`]/[`crate::decode`] API.
+
+use crate::chunk::{self, Chunk, ChunkError};
+use crate::codec::{self, DecodeOptions, EncodeOptions};
+use crate::error::AnsxError;
+use crate::simd::Kernel;
+use crate::stream::{BlockReader, Encoder};
+use libc::{c_int, c_uchar, c_uint, c_void, size_t};
+use std::io::Write;
+use std::slice;
+
+/// Success.
+pub const ANSX_OK: c_int = 0;
+/// A required pointer argument was null.
+pub const ANSX_ERR_INVALID_ARGUMENT: c_int = 1;
+/// Input ended before the stream was complete.
+pub const ANSX_ERR_TRUNCATED: c_int = 2;
+/// A chunk header, method byte or frequency table is malformed.
+pub const ANSX_ERR_BAD_HEADER: c_int = 3;
+/// The coded data does not decode consistently.
+pub const ANSX_ERR_CORRUPT: c_int = 4;
+/// A block decoded to bytes that fail its CRC-32.
+pub const ANSX_ERR_CHECKSUM: c_int = 5;
+/// The output would not fit in the length type of the call (`uint32_t` or `size_t`).
+pub const ANSX_ERR_OUTPUT_TOO_LARGE: c_int = 6;
+/// The caller's buffer is smaller than the result; the required size is reported.
+pub const ANSX_ERR_BUFFER_TOO_SMALL: c_int = 7;
+
+/// Status code reported to C for `e`.
+fn status(e: AnsxError) -> c_int {
+    match e {
+        AnsxError::Truncated => ANSX_ERR_TRUNCATED,
+        AnsxError::BadHeader => ANSX_ERR_BAD_HEADER,
+        AnsxError::Corrupt => ANSX_ERR_CORRUPT,
+        AnsxError::Checksum => ANSX_ERR_CHECKSUM,
+        AnsxError::OutputTooLarge => ANSX_ERR_OUTPUT_TOO_LARGE,
+        AnsxError::InvalidModel => ANSX_ERR_INVALID_ARGUMENT,
+    }
+}
+
+/// Length type of a C entry point: `uint32_t` for the original functions,
+/// `size_t` for the `64` variants.
+trait CLen: Copy {
+    /// Largest length the type can describe.
+    const MAX: usize;
+    fn to_usize(self) -> usize;
+    /// Narrows `n`, which must not exceed [`CLen::MAX`].
+    fn from_usize(n: usize) -> Self;
+}
+
+impl CLen for c_uint {
+    const MAX: usize = c_uint::MAX as usize;
+    fn to_usize(self) -> usize {
+        self as usize
+    }
+    fn from_usize(n: usize) -> Self {
+        debug_assert!(n <= <Self as CLen>::MAX);
+        n as c_uint
+    }
+}
+
+impl CLen for size_t {
+    const MAX: usize = usize::MAX;
+    fn to_usize(self) -> usize {
+        self
+    }
+    fn from_usize(n: usize) -> Self {
+        n
+    }
+}
+
+/// Worst-case encoded size of `len` bytes, or `None` if `L` cannot describe it.
+fn max_len<L: CLen>(len: L) -> Option<L> {
+    let bound = crate::max_encoded_len(len.to_usize());
+    (bound <= L::MAX && bound != usize::MAX).then(|| L::from_usize(bound))
+}
+
+/// Decodes `data`, capping the output at what `L` can describe.
+fn decode_capped<L: CLen>(data: &[u8]) -> crate::Result<Vec<u8>> {
+    crate::decode_with(data, &DecodeOptions::new().max_output(L::MAX))
+}
+
+/// Hands `data` to C as an exact-length heap allocation released by `ansx_free`.
+///
+/// # Safety
+/// `out_len` must be a valid, writable pointer and `data.len()` must not
+/// exceed `L::MAX`.
+unsafe fn into_raw<L: CLen>(data: Vec<u8>, out_len: *mut L) -> *mut c_uchar {
+    let boxed = data.into_boxed_slice();
+    // SAFETY: Guaranteed by the caller (see function safety contract).
+    unsafe { *out_len = L::from_usize(boxed.len()) };
+    Box::into_raw(boxed).cast()
+}
+
+/// Shared body of `ansx_encode`/`ansx_encode64`.
+///
+/// # Safety
+/// As for `ansx_encode`.
+unsafe fn encode_alloc<L: CLen>(input: *const c_uchar, len: L, out_len: *mut L) -> *mut c_uchar {
+    if input.is_null() || out_len.is_null() {
+        return std::ptr::null_mut();
+    }
+    if max_len(len).is_none() {
+        // SAFETY: `out_len` is non-null and writable per the safety contract.
+        unsafe { *out_len = L::from_usize(0) };
+        return std::ptr::null_mut();
+    }
+    // SAFETY: The invariants are upheld by the caller (see function safety contract).
+    let data = unsafe { slice::from_raw_parts(input, len.to_usize()) };
+    // SAFETY: `out_len` is non-null and writable, and the output is at most
+    // `max_len(len)`, which fits in `L`.
+    unsafe { into_raw(crate::encode(data), out_len) }
+}
+
+/// Shared body of `ansx_decode2`/`ansx_decode2_64`.
+///
+/// # Safety
+/// As for `ansx_decode2`.
+unsafe fn decode_alloc<L: CLen>(input: *const c_uchar, len: L, out: *mut *mut c_uchar, out_len: *mut L) -> c_int {
+    if out.is_null() || out_len.is_null() {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    // SAFETY: Both pointers are non-null and writable per the safety contract.
+    unsafe {
+        *out = std::ptr::null_mut();
+        *out_len = L::from_usize(0);
+    }
+    if input.is_null() {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    // SAFETY: The invariants are upheld by the caller (see function safety contract).
+    let data = unsafe { slice::from_raw_parts(input, len.to_usize()) };
+    match decode_capped::<L>(data) {
+        Ok(data) => {
+            // SAFETY: `out` and `out_len` are non-null and writable, and the
+            // output limit keeps the length within `L`.
+            unsafe { *out = into_raw(data, out_len) };
+            ANSX_OK
+        }
+        Err(e) => status(e),
+    }
+}
+
+/// Shared body of `ansx_decode`/`ansx_decode64`.
+///
+/// # Safety
+/// As for `ansx_decode`.
+unsafe fn decode_alloc_or_null<L: CLen>(input: *const c_uchar, len: L, out_len: *mut L) -> *mut c_uchar {
+    if input.is_null() || out_len.is_null() {
+        return std::ptr::null_mut();
+    }
+    let mut out = std::ptr::null_mut();
+    // SAFETY: The invariants are upheld by the caller (see function safety contract).
+    unsafe { decode_alloc(input, len, &mut out, out_len) };
+    out
+}
+
+/// Releases a buffer handed out by `into_raw`.
+///
+/// # Safety
+/// As for `ansx_free`.
+unsafe fn free_raw(ptr: *mut c_void, len: usize) {
+    if ptr.is_null() {
+        return;
+    }
+    // SAFETY: Caller ensures pointer/length come from previous allocation in this crate.
+    unsafe {
+        drop(Box::from_raw(std::ptr::slice_from_raw_parts_mut(ptr as *mut u8, len)));
+    }
+}
+
+/// Compresses `len` bytes at `input` with order-0 rANS.
+///
+/// Returns a buffer of `*out_len` bytes to be released with `ansx_free`, or null
+/// if a pointer argument is null. Also returns null, with `*out_len` set to 0,
+/// if the output might not fit in a `uint32_t` (see `ansx_max_encoded_len`);
+/// use `ansx_encode64` for such inputs.
+///
+/// # Safety
+/// Caller must guarantee `input` points to `len` bytes of readable memory and
+/// `out_len` is a valid, writable pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encode(input: *const c_uchar, len: c_uint, out_len: *mut c_uint) -> *mut c_uchar {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { encode_alloc(input, len, out_len) }
+}
+
+/// Decompresses a buffer produced by `ansx_encode`.
+///
+/// Returns null and sets `*out_len` to 0 if decoding fails for any reason; use
+/// `ansx_decode2` to find out why.
+///
+/// # Safety
+/// Same as `ansx_encode`; caller must provide valid buffer pointers/lengths.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decode(input: *const c_uchar, len: c_uint, out_len: *mut c_uint) -> *mut c_uchar {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { decode_alloc_or_null(input, len, out_len) }
+}
+
+/// Decompresses a buffer produced by `ansx_encode`, reporting why it failed.
+///
+/// Returns `ANSX_OK` and stores a buffer of `*out_len` bytes (to be released
+/// with `ansx_free`) in `*out`, or returns one of the `ANSX_ERR_*` codes with
+/// `*out` set to null and `*out_len` to 0.
+///
+/// # Safety
+/// `input` must point to `len` bytes of readable memory; `out` and `out_len`
+/// must be valid, writable pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decode2(
+    input: *const c_uchar,
+    len: c_uint,
+    out: *mut *mut c_uchar,
+    out_len: *mut c_uint,
+) -> c_int {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { decode_alloc(input, len, out, out_len) }
+}
+
+/// Releases a buffer returned by `ansx_encode`/`ansx_decode`.
+///
+/// # Safety
+/// `ptr` must be a pointer returned by `ansx_encode/decode` with the same `len`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_free(ptr: *mut c_void, len: c_uint) {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { free_raw(ptr, len as usize) }
+}
+
+/// `ansx_encode` with `size_t` lengths, for inputs of 4 GiB and more.
+///
+/// # Safety
+/// Same as `ansx_encode`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encode64(input: *const c_uchar, len: size_t, out_len: *mut size_t) -> *mut c_uchar {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { encode_alloc(input, len, out_len) }
+}
+
+/// `ansx_decode` with `size_t` lengths.
+///
+/// # Safety
+/// Same as `ansx_decode`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decode64(input: *const c_uchar, len: size_t, out_len: *mut size_t) -> *mut c_uchar {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { decode_alloc_or_null(input, len, out_len) }
+}
+
+/// `ansx_decode2` with `size_t` lengths.
+///
+/// # Safety
+/// Same as `ansx_decode2`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decode2_64(
+    input: *const c_uchar,
+    len: size_t,
+    out: *mut *mut c_uchar,
+    out_len: *mut size_t,
+) -> c_int {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { decode_alloc(input, len, out, out_len) }
+}
+
+/// Releases a buffer returned by `ansx_encode64`/`ansx_decode64`/`ansx_decode2_64`.
+///
+/// # Safety
+/// `ptr` must be a pointer returned by one of those functions with the same `len`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_free64(ptr: *mut c_void, len: size_t) {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { free_raw(ptr, len) }
+}
+
+/// Worst-case `ansx_encode`/`ansx_encode_into` output size for `len` input
+/// bytes, or 0 if it does not fit in a `uint32_t`.
+#[unsafe(no_mangle)]
+pub extern "C" fn ansx_max_encoded_len(len: c_uint) -> c_uint {
+    max_len(len).unwrap_or(0)
+}
+
+/// `ansx_max_encoded_len` for the `size_t` functions; 0 if the bound
+/// overflows `size_t`.
+#[unsafe(no_mangle)]
+pub extern "C" fn ansx_max_encoded_len64(len: size_t) -> size_t {
+    max_len(len).unwrap_or(0)
+}
+
+/// The caller's `out` buffer as a slice of `len` bytes.
+///
+/// # Safety
+/// `out` must be valid for `len` writable bytes, or `len` must be 0.
+unsafe fn out_slice<'a>(out: *mut c_uchar, len: usize) -> &'a mut [u8] {
+    if len == 0 {
+        return &mut [];
+    }
+    // SAFETY: Guaranteed by the caller (see function safety contract).
+    unsafe { slice::from_raw_parts_mut(out, len) }
+}
+
+/// Shared body of `ansx_encode_into`/`ansx_encode_into64`.
+///
+/// # Safety
+/// As for `ansx_encode_into`.
+unsafe fn encode_into_raw<L: CLen>(
+    input: *const c_uchar,
+    len: L,
+    out: *mut c_uchar,
+    out_cap: L,
+    out_len: *mut L,
+) -> c_int {
+    if input.is_null() || out_len.is_null() || (out.is_null() && out_cap.to_usize() != 0) {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    if max_len(len).is_none() {
+        // SAFETY: `out_len` is non-null and writable per the safety contract.
+        unsafe { *out_len = L::from_usize(0) };
+        return ANSX_ERR_OUTPUT_TOO_LARGE;
+    }
+    // SAFETY: The invariants are upheld by the caller (see function safety contract).
+    let data = unsafe { slice::from_raw_parts(input, len.to_usize()) };
+    // SAFETY: `out` holds `out_cap` writable bytes per the safety contract.
+    let dst = unsafe { out_slice(out, out_cap.to_usize()) };
+    let written = codec::encode_to_slice(data, dst, &EncodeOptions::default());
+    // SAFETY: `out_len` is non-null and writable; `written` is within
+    // `max_len(len)`, which fits `L`.
+    unsafe { *out_len = L::from_usize(written) };
+    if written > dst.len() { ANSX_ERR_BUFFER_TOO_SMALL } else { ANSX_OK }
+}
+
+/// Shared body of `ansx_decode_into`/`ansx_decode_into64`.
+///
+/// # Safety
+/// As for `ansx_decode_into`.
+unsafe fn decode_into_raw<L: CLen>(
+    input: *const c_uchar,
+    len: L,
+    out: *mut c_uchar,
+    out_cap: L,
+    out_len: *mut L,
+) -> c_int {
+    if input.is_null() || out_len.is_null() || (out.is_null() && out_cap.to_usize() != 0) {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    // SAFETY: `out_len` is non-null and writable per the safety contract.
+    unsafe { *out_len = L::from_usize(0) };
+    // SAFETY: The invariants are upheld by the caller (see function safety contract).
+    let data = unsafe { slice::from_raw_parts(input, len.to_usize()) };
+    let required = match crate::decoded_len(data) {
+        Ok(n) => n,
+        Err(e) => return status(e),
+    };
+    if required > L::MAX {
+        return ANSX_ERR_OUTPUT_TOO_LARGE;
+    }
+    if required > out_cap.to_usize() {
+        // SAFETY: `out_len` is non-null and writable per the safety contract.
+        unsafe { *out_len = L::from_usize(required) };
+        return ANSX_ERR_BUFFER_TOO_SMALL;
+    }
+    // SAFETY: `out` holds `out_cap >= required` writable bytes per the safety contract.
+    let dst = unsafe { out_slice(out, required) };
+    match codec::decode_to_slice(data, dst, Kernel::Auto) {
+        Ok(()) => {
+            // SAFETY: `out_len` is non-null and writable per the safety contract.
+            unsafe { *out_len = L::from_usize(required) };
+            ANSX_OK
+        }
+        Err(e) => status(e),
+    }
+}
+
+/// Compresses `len` bytes at `input` into the caller's buffer `out` of
+/// `out_cap` bytes, writing the encoded size to `*out_len`.
+///
+/// A buffer of `ansx_max_encoded_len(len)` bytes always suffices. If `out_cap`
+/// is too small, returns `ANSX_ERR_BUFFER_TOO_SMALL` with the required size in
+/// `*out_len`, leaving the contents of `out` unspecified; `out` may be null
+/// when `out_cap` is 0. Returns
+/// `ANSX_ERR_OUTPUT_TOO_LARGE` without reading the input if
+/// `ansx_max_encoded_len(len)` is 0.
+///
+/// # Safety
+/// `input` must point to `len` readable bytes, `out` to `out_cap` writable
+/// bytes, and `out_len` must be a valid, writable pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encode_into(
+    input: *const c_uchar,
+    len: c_uint,
+    out: *mut c_uchar,
+    out_cap: c_uint,
+    out_len: *mut c_uint,
+) -> c_int {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { encode_into_raw(input, len, out, out_cap, out_len) }
+}
+
+/// Decompresses a buffer produced by `ansx_encode` into the caller's buffer
+/// `out` of `out_cap` bytes, writing the decoded size to `*out_len`.
+///
+/// If `out_cap` is too small, returns `ANSX_ERR_BUFFER_TOO_SMALL` with the
+/// required size in `*out_len` before decoding anything; `out` may be null
+/// when `out_cap` is 0. Other failures return the `ansx_decode2` codes with
+/// `*out_len` set to 0.
+///
+/// # Safety
+/// Same as `ansx_encode_into`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decode_into(
+    input: *const c_uchar,
+    len: c_uint,
+    out: *mut c_uchar,
+    out_cap: c_uint,
+    out_len: *mut c_uint,
+) -> c_int {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { decode_into_raw(input, len, out, out_cap, out_len) }
+}
+
+/// `ansx_encode_into` with `size_t` lengths.
+///
+/// # Safety
+/// Same as `ansx_encode_into`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encode_into64(
+    input: *const c_uchar,
+    len: size_t,
+    out: *mut c_uchar,
+    out_cap: size_t,
+    out_len: *mut size_t,
+) -> c_int {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { encode_into_raw(input, len, out, out_cap, out_len) }
+}
+
+/// `ansx_decode_into` with `size_t` lengths.
+///
+/// # Safety
+/// Same as `ansx_decode_into`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decode_into64(
+    input: *const c_uchar,
+    len: size_t,
+    out: *mut c_uchar,
+    out_cap: size_t,
+    out_len: *mut size_t,
+) -> c_int {
+    // SAFETY: Same contract (see function safety contract).
+    unsafe { decode_into_raw(input, len, out, out_cap, out_len) }
+}
+
+/// Opaque streaming encoder behind `ansx_encoder*`.
+pub struct EncoderHandle {
+    // `None` once finished.
+    encoder: Option<Encoder<Vec<u8>>>,
+    out: Vec<u8>,
+    pos: usize,
+}
+
+/// Opaque streaming decoder behind `ansx_decoder*`.
+pub struct DecoderHandle {
+    input: Vec<u8>,
+    consumed: usize,
+    reader: BlockReader,
+    error: Option<AnsxError>,
+}
+
+/// Copies pending bytes of `data[*pos..]` into `out`, compacting `data` once drained.
+///
+/// # Safety
+/// `out` must be valid for `cap` writable bytes and `out_len` must be a
+/// valid, writable pointer.
+unsafe fn drain_into(data: &mut Vec<u8>, pos: &mut usize, out: *mut c_uchar, cap: size_t, out_len: *mut size_t) {
+    let n = (data.len() - *pos).min(cap);
+    if n > 0 {
+        // SAFETY: `out` holds at least `cap >= n` writable bytes, disjoint from `data`.
+        unsafe { std::ptr::copy_nonoverlapping(data[*pos..].as_ptr(), out, n) };
+    }
+    *pos += n;
+    if *pos == data.len() {
+        data.clear();
+        *pos = 0;
+    }
+    // SAFETY: Guaranteed by the caller (see function safety contract).
+    unsafe { *out_len = n };
+}
+
+/// Creates a streaming encoder with default settings. Release it with
+/// `ansx_encoder_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn ansx_encoder_new() -> *mut EncoderHandle {
+    let handle = EncoderHandle { encoder: Some(Encoder::new(Vec::new())), out: Vec::new(), pos: 0 };
+    Box::into_raw(Box::new(handle))
+}
+
+/// Feeds `len` bytes to the encoder. Completed chunks become available to
+/// `ansx_encoder_read`.
+///
+/// Returns `ANSX_ERR_INVALID_ARGUMENT` for null pointers or after
+/// `ansx_encoder_finish`.
+///
+/// # Safety
+/// `enc` must come from `ansx_encoder_new` and `input` must point to `len`
+/// readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encoder_write(enc: *mut EncoderHandle, input: *const c_uchar, len: size_t) -> c_int {
+    // SAFETY: `enc` is null or a live handle per the safety contract.
+    let Some(h) = (unsafe { enc.as_mut() }) else {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    };
+    let Some(encoder) = h.encoder.as_mut() else {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    };
+    if input.is_null() {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    // SAFETY: The invariants are upheld by the caller (see function safety contract).
+    let data = unsafe { slice::from_raw_parts(input, len) };
+    encoder.write_all(data).expect("writing to a Vec cannot fail");
+    h.out.append(encoder.get_mut());
+    ANSX_OK
+}
+
+/// Encodes the final partial block. No more input is accepted afterwards.
+///
+/// # Safety
+/// `enc` must come from `ansx_encoder_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encoder_finish(enc: *mut EncoderHandle) -> c_int {
+    // SAFETY: `enc` is null or a live handle per the safety contract.
+    let Some(h) = (unsafe { enc.as_mut() }) else {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    };
+    if let Some(encoder) = h.encoder.take() {
+        let mut rest = encoder.finish().expect("writing to a Vec cannot fail");
+        h.out.append(&mut rest);
+    }
+    ANSX_OK
+}
+
+/// Moves up to `cap` bytes of encoded output into `out`, storing the count in
+/// `*out_len`. A count of 0 means no output is pending.
+///
+/// # Safety
+/// `enc` must come from `ansx_encoder_new`, `out` must point to `cap`
+/// writable bytes and `out_len` must be a valid, writable pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encoder_read(
+    enc: *mut EncoderHandle,
+    out: *mut c_uchar,
+    cap: size_t,
+    out_len: *mut size_t,
+) -> c_int {
+    // SAFETY: `enc` is null or a live handle per the safety contract.
+    let Some(h) = (unsafe { enc.as_mut() }) else {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    };
+    if out_len.is_null() || (out.is_null() && cap != 0) {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    // SAFETY: `out`/`cap` and `out_len` are valid per the safety contract.
+    unsafe { drain_into(&mut h.out, &mut h.pos, out, cap, out_len) };
+    ANSX_OK
+}
+
+/// Releases an encoder, discarding any unread output.
+///
+/// # Safety
+/// `enc` must be null or come from `ansx_encoder_new`, and not be used again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_encoder_free(enc: *mut EncoderHandle) {
+    if !enc.is_null() {
+        // SAFETY: `enc` was allocated by `ansx_encoder_new` per the safety contract.
+        drop(unsafe { Box::from_raw(enc) });
+    }
+}
+
+/// Creates a streaming decoder with default settings. Release it with
+/// `ansx_decoder_free`.
+#[unsafe(no_mangle)]
+pub extern "C" fn ansx_decoder_new() -> *mut DecoderHandle {
+    let handle = DecoderHandle { input: Vec::new(), consumed: 0, reader: BlockReader::new(DecodeOptions::new()), error: None };
+    Box::into_raw(Box::new(handle))
+}
+
+/// Feeds `len` bytes of compressed input to the decoder.
+///
+/// # Safety
+/// `dec` must come from `ansx_decoder_new` and `input` must point to `len`
+/// readable bytes.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decoder_write(dec: *mut DecoderHandle, input: *const c_uchar, len: size_t) -> c_int {
+    // SAFETY: `dec` is null or a live handle per the safety contract.
+    let Some(h) = (unsafe { dec.as_mut() }) else {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    };
+    if input.is_null() {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    h.input.drain(..h.consumed);
+    h.consumed = 0;
+    // SAFETY: The invariants are upheld by the caller (see function safety contract).
+    h.input.extend_from_slice(unsafe { slice::from_raw_parts(input, len) });
+    ANSX_OK
+}
+
+impl DecoderHandle {
+    /// Decodes buffered chunks into `out` until it is full or a whole chunk is
+    /// not yet available.
+    fn read(&mut self, out: &mut [u8]) -> Result<usize, AnsxError> {
+        if let Some(e) = self.error {
+            return Err(e);
+        }
+        let mut written = 0;
+        while written < out.len() {
+            if self.reader.is_drained() {
+                match Chunk::parse(&self.input[self.consumed..]) {
+                    Ok((chunk, rest)) => {
+                        let next = self.input.len() - rest.len();
+                        if let Err(e) = self.reader.push(&chunk) {
+                            self.error = Some(e);
+                            return Err(e);
+                        }
+                        self.consumed = next;
+                    }
+                    Err(ChunkError::Truncated) => break,
+                    Err(e) => {
+                        self.error = Some(e.into());
+                        return Err(e.into());
+                    }
+                }
+            }
+            written += self.reader.read_into(&mut out[written..]);
+        }
+        Ok(written)
+    }
+}
+
+/// Decodes buffered input into `out` (up to `cap` bytes), storing the count
+/// in `*out_len`. A count below `cap` means more input is needed.
+///
+/// Returns an `ansx_decode2` status code; errors are sticky.
+///
+/// # Safety
+/// `dec` must come from `ansx_decoder_new`, `out` must point to `cap`
+/// writable bytes and `out_len` must be a valid, writable pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decoder_read(
+    dec: *mut DecoderHandle,
+    out: *mut c_uchar,
+    cap: size_t,
+    out_len: *mut size_t,
+) -> c_int {
+    // SAFETY: `dec` is null or a live handle per the safety contract.
+    let Some(h) = (unsafe { dec.as_mut() }) else {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    };
+    if out_len.is_null() || (out.is_null() && cap != 0) {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    }
+    // SAFETY: `out_len` is non-null and writable per the safety contract.
+    unsafe { *out_len = 0 };
+    if cap == 0 {
+        return h.error.map_or(ANSX_OK, status);
+    }
+    // SAFETY: `out` is valid for `cap` writable bytes per the safety contract.
+    let buf = unsafe { slice::from_raw_parts_mut(out, cap) };
+    match h.read(buf) {
+        Ok(n) => {
+            // SAFETY: `out_len` is non-null and writable per the safety contract.
+            unsafe { *out_len = n };
+            ANSX_OK
+        }
+        Err(e) => status(e),
+    }
+}
+
+/// Declares the end of input. Returns `ANSX_ERR_TRUNCATED` if the buffered
+/// input stops inside a chunk, another error code if it is malformed, and
+/// `ANSX_OK` otherwise. Decoded output may still be read afterwards.
+///
+/// # Safety
+/// `dec` must come from `ansx_decoder_new`.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decoder_finish(dec: *mut DecoderHandle) -> c_int {
+    // SAFETY: `dec` is null or a live handle per the safety contract.
+    let Some(h) = (unsafe { dec.as_ref() }) else {
+        return ANSX_ERR_INVALID_ARGUMENT;
+    };
+    if let Some(e) = h.error {
+        return status(e);
+    }
+    match chunk::chunks(&h.input[h.consumed..]).find_map(|c| c.err()) {
+        Some(e) => status(e.into()),
+        None => ANSX_OK,
+    }
+}
+
+/// Releases a decoder.
+///
+/// # Safety
+/// `dec` must be null or come from `ansx_decoder_new`, and not be used again.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn ansx_decoder_free(dec: *mut DecoderHandle) {
+    if !dec.is_null() {
+        // SAFETY: `dec` was allocated by `ansx_decoder_new` per the safety contract.
+        drop(unsafe { Box::from_raw(dec) });
+    }
+}
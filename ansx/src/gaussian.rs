@@ -0,0 +1,254 @@
+//! Parametric Gaussian model for 16-bit symbols (design doc §2.2, Axiom 3).
+//!
+//! Each 16 KB block of symbols is coded with a table built from the block's
+//! (μ, σ). Giving all 65536 symbols a frequency of at least 1 would exhaust a
+//! 2¹⁶ table, so the table covers a window of `μ ± 5σ` (capped at `max_span`
+//! symbols) plus one escape symbol. Values outside the window are coded as the
+//! escape followed by the raw 16-bit value, so any input round-trips.
+//!
+//! Table construction uses only IEEE-754 basic arithmetic (no libm), so encoder
+//! and decoder derive bit-identical tables on every platform, with or without
+//! the AVX2 kernel.
+//!
+//! Stream layout:
+//!
+//! ```text
+//! symbol_count: u32 LE
+//! per block:    cmpr_len: u32 LE, rANS stream (cmpr_len bytes)
+//! ```
+
+use crate::error::{AnsxError, Result};
+use crate::rans::{FreqTable, PROB_SCALE, RansDecoder, RansEncoder};
+use crate::simd::{self, Kernel};
+
+/// Symbols per block: 16 KB of 16-bit symbols.
+pub const BLOCK_SYMBOLS: usize = 8192;
+/// Default cap on the number of in-window symbols.
+pub const DEFAULT_MAX_SPAN: u32 = 16384;
+/// Half-width of the table window in standard deviations.
+const WINDOW_SIGMAS: f64 = 5.0;
+
+/// Per-block model parameters, typically produced by FlowNet.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BlockParams {
+    /// Mean symbol value.
+    pub mu: f32,
+    /// Standard deviation; must be finite and positive.
+    pub sigma: f32,
+}
+
+/// Frequency table for one block: window `lo..=hi` followed by an escape symbol.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GaussianTable {
+    lo: u16,
+    hi: u16,
+    table: FreqTable,
+}
+
+impl GaussianTable {
+    /// Symbols covered directly by the table.
+    pub fn window(&self) -> std::ops::RangeInclusive<u16> {
+        self.lo..=self.hi
+    }
+
+    /// Underlying normalized table; the last entry is the escape symbol.
+    pub fn freq_table(&self) -> &FreqTable {
+        &self.table
+    }
+
+    fn escape(&self) -> usize {
+        self.table.len() - 1
+    }
+
+    fn index_of(&self, sym: u16) -> usize {
+        if (self.lo..=self.hi).contains(&sym) {
+            (sym - self.lo) as usize
+        } else {
+            self.escape()
+        }
+    }
+
+    /// Ideal cost of coding `sym` in bits, including the raw 16 bits that
+    /// follow an escape.
+    pub(crate) fn cost_bits(&self, sym: u16) -> f64 {
+        let idx = self.index_of(sym);
+        let bits = (PROB_SCALE as f64 / self.table.freq(idx) as f64).log2();
+        if idx == self.escape() { bits + 16.0 } else { bits }
+    }
+}
+
+/// Builds [`GaussianTable`]s from (μ, σ).
+#[derive(Debug, Clone, Copy)]
+pub struct GaussianTableBuilder {
+    max_span: u32,
+    kernel: Kernel,
+}
+
+impl Default for GaussianTableBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl GaussianTableBuilder {
+    /// Creates a builder with the default window cap.
+    pub fn new() -> Self {
+        Self { max_span: DEFAULT_MAX_SPAN, kernel: Kernel::Auto }
+    }
+
+    /// Caps the number of in-window symbols (clamped to `1..PROB_SCALE`).
+    /// Encoder and decoder must agree on this value.
+    pub fn max_span(mut self, span: u32) -> Self {
+        self.max_span = span.clamp(1, PROB_SCALE - 1);
+        self
+    }
+
+    /// Selects the weight/quantization kernel. Every kernel yields the same table.
+    pub fn kernel(mut self, kernel: Kernel) -> Self {
+        self.kernel = kernel;
+        self
+    }
+
+    /// Builds the table for one block.
+    pub fn build(&self, params: BlockParams) -> Result<GaussianTable> {
+        let mu = params.mu as f64;
+        let sigma = params.sigma as f64;
+        if !mu.is_finite() || !sigma.is_finite() || sigma <= 0.0 {
+            return Err(AnsxError::InvalidModel);
+        }
+
+        let centre = mu.clamp(0.0, 65535.0);
+        let half = (WINDOW_SIGMAS * sigma).min(self.max_span as f64 / 2.0);
+        let mut lo = (centre - half).floor().max(0.0) as u32;
+        let mut hi = (centre + half).ceil().min(65535.0) as u32;
+        if hi - lo + 1 > self.max_span {
+            // Trim whichever side lies further from the centre.
+            let excess = hi - lo + 1 - self.max_span;
+            if centre - lo as f64 >= hi as f64 - centre {
+                lo += excess;
+            } else {
+                hi -= excess;
+            }
+        }
+
+        let span = (hi - lo + 1) as usize;
+        let mut weights = vec![0.0; span];
+        simd::gaussian_weights(self.kernel, lo, mu, sigma, &mut weights);
+        let total: f64 = weights.iter().sum();
+
+        // Every entry (window plus escape) gets 1; the rest is shared out in
+        // proportion to the weights, and rounding leftovers go to the mode.
+        let spare = PROB_SCALE - span as u32 - 1;
+        let mut freq = vec![1u32; span + 1];
+        if total > 0.0 {
+            simd::quantize(self.kernel, &weights, total, spare, &mut freq[..span]);
+        }
+        let assigned: u32 = freq.iter().sum();
+        let mode = (centre.round() as u32).clamp(lo, hi) - lo;
+        // Per-entry rounding must not overshoot the scale; if it does, the
+        // table cannot be normalized and the model is rejected.
+        freq[mode as usize] += PROB_SCALE.checked_sub(assigned).ok_or(AnsxError::InvalidModel)?;
+
+        let table = FreqTable::from_freqs(freq).ok_or(AnsxError::InvalidModel)?;
+        Ok(GaussianTable { lo: lo as u16, hi: hi as u16, table })
+    }
+}
+
+fn tables(builder: &GaussianTableBuilder, params: &[BlockParams], count: usize) -> Result<Vec<GaussianTable>> {
+    if params.len() != count.div_ceil(BLOCK_SYMBOLS) {
+        return Err(AnsxError::InvalidModel);
+    }
+    params.iter().map(|&p| builder.build(p)).collect()
+}
+
+/// Encodes 16-bit symbols with one (μ, σ) per [`BLOCK_SYMBOLS`] block.
+///
+/// `params.len()` must equal the number of blocks, `symbols.len().div_ceil(BLOCK_SYMBOLS)`.
+pub fn encode_gaussian(symbols: &[u16], params: &[BlockParams]) -> Result<Vec<u8>> {
+    encode_gaussian_with(&GaussianTableBuilder::new(), symbols, params)
+}
+
+/// [`encode_gaussian`] with a custom table builder.
+pub fn encode_gaussian_with(builder: &GaussianTableBuilder, symbols: &[u16], params: &[BlockParams]) -> Result<Vec<u8>> {
+    let count = u32::try_from(symbols.len()).map_err(|_| AnsxError::InvalidModel)?;
+    let tables = tables(builder, params, symbols.len())?;
+
+    let mut out = count.to_le_bytes().to_vec();
+    for (block, table) in symbols.chunks(BLOCK_SYMBOLS).zip(&tables) {
+        let freqs = table.freq_table();
+        let mut enc = RansEncoder::new();
+        for &s in block.iter().rev() {
+            let idx = table.index_of(s);
+            if idx == table.escape() {
+                enc.put(s as u32, 1);
+            }
+            enc.put_symbol(freqs, idx);
+        }
+        let stream = enc.finish();
+        out.extend_from_slice(&(stream.len() as u32).to_le_bytes());
+        out.extend_from_slice(&stream);
+    }
+    Ok(out)
+}
+
+/// Decodes a stream produced by [`encode_gaussian`] using the same `params`.
+pub fn decode_gaussian(input: &[u8], params: &[BlockParams]) -> Result<Vec<u16>> {
+    decode_gaussian_with(&GaussianTableBuilder::new(), input, params)
+}
+
+/// [`decode_gaussian`] with a custom table builder.
+pub fn decode_gaussian_with(builder: &GaussianTableBuilder, input: &[u8], params: &[BlockParams]) -> Result<Vec<u16>> {
+    let head = input.get(..4).ok_or(AnsxError::Truncated)?;
+    let count = u32::from_le_bytes(head.try_into().unwrap()) as usize;
+    let tables = tables(builder, params, count)?;
+
+    let mut out = Vec::with_capacity(count);
+    let mut rest = &input[4..];
+    for (i, table) in tables.iter().enumerate() {
+        let len_bytes = rest.get(..4).ok_or(AnsxError::Truncated)?;
+        let cmpr_len = u32::from_le_bytes(len_bytes.try_into().unwrap()) as usize;
+        let stream = rest.get(4..4 + cmpr_len).ok_or(AnsxError::Truncated)?;
+        rest = &rest[4 + cmpr_len..];
+
+        let freqs = table.freq_table();
+        let n = BLOCK_SYMBOLS.min(count - i * BLOCK_SYMBOLS);
+        let mut dec = RansDecoder::new(stream)?;
+        for _ in 0..n {
+            let idx = dec.get_symbol(freqs)?;
+            let sym = if idx == table.escape() {
+                let raw = dec.peek();
+                dec.advance(raw, 1)?;
+                raw as u16
+            } else {
+                table.lo + idx as u16
+            };
+            out.push(sym);
+        }
+        if !dec.is_finished() {
+            return Err(AnsxError::Corrupt);
+        }
+    }
+    if rest.is_empty() { Ok(out) } else { Err(AnsxError::Corrupt) }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::simd::exp_neg;
+
+    #[test]
+    fn exp_neg_matches_std() {
+        for i in 0..200 {
+            let x = i as f64 * 0.173;
+            let rel = (exp_neg(x) - (-x).exp()).abs() / (-x).exp();
+            assert!(rel < 1e-14, "x = {x}, rel = {rel}");
+        }
+    }
+
+    #[test]
+    fn window_is_capped() {
+        let table = GaussianTableBuilder::new().max_span(100).build(BlockParams { mu: 30_000.0, sigma: 5_000.0 }).unwrap();
+        assert_eq!(table.window().count(), 100);
+        assert!(table.window().contains(&30_000));
+    }
+}
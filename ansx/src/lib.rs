@@ -1,45 +1,48 @@
 #![deny(unsafe_op_in_unsafe_fn)]
 #![deny(clippy::undocumented_unsafe_blocks)]
 
-//! ANS-X pass-through stub (identity coder)
+//! ANS-X entropy coder: order-0 scalar rANS.
+//!
+//! Rust callers use [`encode`]/[`decode`], or [`Encoder`]/[`Decoder`] to
+//! stream inputs larger than memory; C and C++ callers use the `ansx_*`
+//! functions declared in `include/ansx.h`, which wrap the same API.
+//! FlowNet latents are coded with the parametric model in [`gaussian`].
+//! With the `parallel` feature, `parallel` codes blocks across threads.
+//! [`analyze`] estimates each block's cost under every model without encoding.
+//!
+//! ```
+//! let packed = ansx::encode(b"abracadabra");
+//! assert_eq!(ansx::decode(&packed).unwrap(), b"abracadabra");
+//! ```
 
-use libc::{c_uint, c_uchar, c_void};
-use std::slice;
+pub mod adaptive;
+pub mod analyze;
+pub mod bitio;
+pub mod budget;
+pub mod checksum;
+pub mod chunk;
+pub mod codec;
+pub mod error;
+pub mod ffi;
+pub mod gaussian;
+#[cfg(feature = "parallel")]
+pub mod parallel;
+pub mod rans;
+pub mod simd;
+pub mod stream;
+pub mod tans;
+pub mod zeta;
 
-/// SAFETY: Caller must guarantee `input` points to `len` bytes of readable memory and
-/// `out_len` is a valid, writable pointer.
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn ansx_encode(input: *const c_uchar, len: c_uint, out_len: *mut c_uint) -> *mut c_uchar {
-    if input.is_null() || out_len.is_null() {
-        return std::ptr::null_mut();
-    }
-    // SAFETY: The invariants are upheld by the caller (see function safety contract).
-    unsafe {
-        let data = slice::from_raw_parts(input, len as usize);
-        let mut vec = data.to_vec();
-        let ptr = vec.as_mut_ptr();
-        let length = vec.len() as c_uint;
-        std::mem::forget(vec);
-        *out_len = length;
-        ptr
-    }
-}
-
-/// SAFETY: Same as `ansx_encode`; caller must provide valid buffer pointers/lengths.
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn ansx_decode(input: *const c_uchar, len: c_uint, out_len: *mut c_uint) -> *mut c_uchar {
-    // Identity decode
-    unsafe { ansx_encode(input, len, out_len) }
-}
-
-/// SAFETY: `ptr` must be a pointer returned by `ansx_encode/decode` with the same `len`.
-#[unsafe(no_mangle)]
-pub unsafe extern "C" fn ansx_free(ptr: *mut c_void, len: c_uint) {
-    if ptr.is_null() {
-        return;
-    }
-    // SAFETY: Caller ensures pointer/length come from previous allocation in this crate.
-    unsafe {
-        Vec::<u8>::from_raw_parts(ptr as *mut u8, len as usize, len as usize);
-    }
-} 
\ No newline at end of file
+pub use codec::{
+    Backend, DecodeOptions, EncodeOptions, Interleave, Model, decode, decode_into, decode_into_with, decode_with,
+    decoded_len, encode, encode_into, encode_into_with, encode_with, max_encoded_len,
+};
+pub use analyze::{BlockReport, analyze};
+pub use budget::{BudgetOptions, BudgetPolicy, BudgetReport, encode_verified};
+pub use error::{AnsxError, Result};
+pub use ffi::*;
+pub use gaussian::{BlockParams, GaussianTableBuilder, decode_gaussian, encode_gaussian};
+#[cfg(feature = "parallel")]
+pub use parallel::{decode_parallel, decode_parallel_with, encode_parallel, encode_parallel_with};
+pub use simd::Kernel;
+pub use stream::{Decoder, Encoder};
@@ -0,0 +1,88 @@
+//! Block-parallel encode and decode on the rayon thread pool (docs/ansx_design.md,
+//! "Future Extensions").
+//!
+//! Every block is coded on its own; the only link between neighbours is the
+//! repeat flag, which compares against the previous *input* block when
+//! encoding and is resolved in a final serial pass when decoding. Output is
+//! byte-identical to [`crate::encode_with`] whatever the thread count. Work
+//! runs on the current rayon pool, so wrap calls in
+//! [`rayon::ThreadPool::install`] to choose the number of threads.
+
+use crate::chunk::{self, Chunk, DEFAULT_BLOCK_SIZE, FLAG_REPEAT};
+use crate::codec::{self, DecodeOptions, EncodeOptions};
+use crate::error::{AnsxError, Result};
+use rayon::prelude::*;
+
+const BLOCK: usize = DEFAULT_BLOCK_SIZE as usize;
+
+/// [`crate::encode`] with blocks spread across threads.
+pub fn encode_parallel(data: &[u8]) -> Vec<u8> {
+    encode_parallel_with(data, &EncodeOptions::default())
+}
+
+/// [`crate::encode_with`] with blocks spread across threads.
+pub fn encode_parallel_with(data: &[u8], options: &EncodeOptions) -> Vec<u8> {
+    let chunks: Vec<Vec<u8>> = data
+        .par_chunks(BLOCK)
+        .enumerate()
+        .map_init(Vec::new, |payload, (i, block)| {
+            let prev = i.checked_sub(1).map(|p| &data[p * BLOCK..i * BLOCK]);
+            let mut out = Vec::new();
+            codec::encode_block(block, prev, options, payload, &mut out);
+            out
+        })
+        .collect();
+    chunks.concat()
+}
+
+/// [`crate::decode`] with blocks spread across threads.
+pub fn decode_parallel(input: &[u8]) -> Result<Vec<u8>> {
+    decode_parallel_with(input, &DecodeOptions::default())
+}
+
+/// [`crate::decode_with`] with blocks spread across threads. Reports the same
+/// error as the serial decoder: the one from the earliest failing chunk.
+pub fn decode_parallel_with(input: &[u8], options: &DecodeOptions) -> Result<Vec<u8>> {
+    let chunks = chunk::chunks(input).collect::<std::result::Result<Vec<Chunk<'_>>, _>>()?;
+    let total = chunks.iter().map(|c| c.header.block_size as usize).sum::<usize>();
+    if total > options.max_output {
+        return Err(AnsxError::OutputTooLarge);
+    }
+
+    let mut out = vec![0u8; total];
+    let mut slices = Vec::with_capacity(chunks.len());
+    let mut rest = &mut out[..];
+    for c in &chunks {
+        let (head, tail) = rest.split_at_mut(c.header.block_size as usize);
+        slices.push(head);
+        rest = tail;
+    }
+
+    let kernel = options.kernel;
+    let results: Vec<Result<()>> = chunks
+        .par_iter()
+        .enumerate()
+        .zip(slices)
+        .map(|((i, c), dst)| {
+            if c.header.parity_flag & FLAG_REPEAT != 0 {
+                // Filled in below, once the previous block is known.
+                let prev_len = i.checked_sub(1).map(|p| chunks[p].header.block_size);
+                return if prev_len == Some(c.header.block_size) { Ok(()) } else { Err(AnsxError::Corrupt) };
+            }
+            codec::decode_block_to(c, None, kernel, dst)
+        })
+        .collect();
+    results.into_iter().collect::<Result<()>>()?;
+
+    let mut start = 0;
+    let mut prev = 0..0;
+    for c in &chunks {
+        let len = c.header.block_size as usize;
+        if c.header.parity_flag & FLAG_REPEAT != 0 {
+            out.copy_within(prev.clone(), start);
+        }
+        prev = start..start + len;
+        start += len;
+    }
+    Ok(out)
+}
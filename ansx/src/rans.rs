@@ -0,0 +1,386 @@
+//! Scalar rANS state machine (design doc Phase A).
+//!
+//! 32-bit state kept in `[RANS_L, RANS_L << 8)`, byte-wise renormalization and
+//! cumulative frequencies quantized to `PROB_SCALE` (2¹⁶).
+
+use crate::error::{AnsxError, Result};
+use crate::simd::Kernel;
+
+/// Precision of the cumulative frequency table in bits.
+pub const PROB_BITS: u32 = 16;
+/// Sum of all frequencies in a normalized table.
+pub const PROB_SCALE: u32 = 1 << PROB_BITS;
+/// Lower bound of the normalized encoder/decoder state.
+pub const RANS_L: u32 = 1 << 23;
+
+/// Normalized frequency table over a dense alphabet `0..len`.
+///
+/// Frequencies sum to exactly `PROB_SCALE`; `cum[s]` is the start of symbol `s`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FreqTable {
+    freq: Vec<u32>,
+    cum: Vec<u32>,
+}
+
+impl FreqTable {
+    /// Builds a table from raw symbol counts.
+    ///
+    /// Every symbol with a nonzero count receives a frequency of at least 1.
+    /// Returns `None` if all counts are zero or there are more used symbols
+    /// than `PROB_SCALE` slots.
+    pub fn from_counts(counts: &[u32]) -> Option<Self> {
+        Self::from_freqs(normalize(counts, PROB_SCALE)?)
+    }
+
+    /// Wraps already-normalized frequencies. Returns `None` unless they sum
+    /// to exactly `PROB_SCALE`.
+    pub fn from_freqs(freq: Vec<u32>) -> Option<Self> {
+        let mut cum = Vec::with_capacity(freq.len() + 1);
+        let mut acc: u32 = 0;
+        cum.push(0);
+        for &f in &freq {
+            acc = acc.checked_add(f)?;
+            cum.push(acc);
+        }
+        (acc == PROB_SCALE).then_some(Self { freq, cum })
+    }
+
+    /// Number of symbols in the alphabet.
+    pub fn len(&self) -> usize {
+        self.freq.len()
+    }
+
+    /// Returns `true` if the alphabet is empty.
+    pub fn is_empty(&self) -> bool {
+        self.freq.is_empty()
+    }
+
+    /// Frequency of `sym`.
+    #[inline]
+    pub fn freq(&self, sym: usize) -> u32 {
+        self.freq[sym]
+    }
+
+    /// Cumulative frequency (start of the slot range) of `sym`.
+    #[inline]
+    pub fn cum(&self, sym: usize) -> u32 {
+        self.cum[sym]
+    }
+
+    /// All frequencies, indexed by symbol.
+    pub fn freqs(&self) -> &[u32] {
+        &self.freq
+    }
+
+    /// Cumulative frequencies, `len() + 1` entries ending in `PROB_SCALE`.
+    pub(crate) fn cum_table(&self) -> &[u32] {
+        &self.cum
+    }
+
+    /// Finds the symbol whose slot range contains `slot` (binary search).
+    #[inline]
+    pub fn symbol_at(&self, slot: u32) -> usize {
+        self.cum.partition_point(|&c| c <= slot) - 1
+    }
+}
+
+/// Scales `counts` to frequencies summing to exactly `scale`, giving every
+/// symbol with a nonzero count a frequency of at least 1.
+///
+/// Returns `None` if all counts are zero or there are more used symbols than
+/// `scale` slots.
+pub(crate) fn normalize(counts: &[u32], scale: u32) -> Option<Vec<u32>> {
+    let total: u64 = counts.iter().map(|&c| c as u64).sum();
+    let used = counts.iter().filter(|&&c| c > 0).count();
+    if total == 0 || used > scale as usize {
+        return None;
+    }
+
+    let mut freq: Vec<u32> = counts
+        .iter()
+        .map(|&c| if c == 0 { 0 } else { ((c as u64 * scale as u64 / total) as u32).max(1) })
+        .collect();
+
+    // Fix up rounding so the table sums to `scale`. Surplus goes to (and
+    // deficit is taken from) the most probable symbol, which changes its
+    // code length the least.
+    let mut sum: i64 = freq.iter().map(|&f| f as i64).sum();
+    while sum != scale as i64 {
+        let (best, &f) = freq.iter().enumerate().max_by_key(|&(i, &f)| (f, usize::MAX - i))?;
+        if sum < scale as i64 {
+            freq[best] += (scale as i64 - sum) as u32;
+            sum = scale as i64;
+        } else {
+            let take = (sum - scale as i64).min(f as i64 - 1);
+            freq[best] -= take as u32;
+            sum -= take;
+        }
+    }
+    Some(freq)
+}
+
+/// Encodes one symbol into `state`, spilling renormalization bytes to `out`.
+#[inline(always)]
+fn put_state(state: &mut u32, out: &mut Vec<u8>, start: u32, freq: u32) {
+    debug_assert!(freq > 0 && start + freq <= PROB_SCALE);
+    let x_max = ((RANS_L >> PROB_BITS) << 8) * freq;
+    let mut x = *state;
+    while x >= x_max {
+        out.push(x as u8);
+        x >>= 8;
+    }
+    *state = ((x / freq) << PROB_BITS) + (x % freq) + start;
+}
+
+/// Decodes the symbol occupying `[start, start + freq)` out of `state`,
+/// refilling from `buf[*pos..]`.
+#[inline(always)]
+fn advance_state(state: &mut u32, buf: &[u8], pos: &mut usize, start: u32, freq: u32) -> Result<()> {
+    let mut x = freq * (*state >> PROB_BITS) + (*state & (PROB_SCALE - 1)) - start;
+    while x < RANS_L {
+        let b = *buf.get(*pos).ok_or(AnsxError::Truncated)?;
+        *pos += 1;
+        x = (x << 8) | b as u32;
+    }
+    *state = x;
+    Ok(())
+}
+
+/// Reads `N` little-endian initial states from the front of `buf`.
+fn read_states<const N: usize>(buf: &[u8]) -> Result<[u32; N]> {
+    let head = buf.get(..4 * N).ok_or(AnsxError::Truncated)?;
+    let mut states = [0u32; N];
+    for (state, bytes) in states.iter_mut().zip(head.chunks_exact(4)) {
+        *state = u32::from_le_bytes(bytes.try_into().unwrap());
+        if !(RANS_L..RANS_L << 8).contains(state) {
+            return Err(AnsxError::Corrupt);
+        }
+    }
+    Ok(states)
+}
+
+/// rANS encoder. Symbols must be fed in *reverse* order.
+pub struct RansEncoder {
+    state: u32,
+    // Bytes in reverse stream order; flipped once in `finish`.
+    out: Vec<u8>,
+}
+
+impl Default for RansEncoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl RansEncoder {
+    /// Creates an encoder in its initial state.
+    pub fn new() -> Self {
+        Self { state: RANS_L, out: Vec::new() }
+    }
+
+    /// Encodes one symbol occupying `[start, start + freq)` of `PROB_SCALE`.
+    #[inline]
+    pub fn put(&mut self, start: u32, freq: u32) {
+        put_state(&mut self.state, &mut self.out, start, freq);
+    }
+
+    /// Encodes `sym` using `table`.
+    #[inline]
+    pub fn put_symbol(&mut self, table: &FreqTable, sym: usize) {
+        self.put(table.cum(sym), table.freq(sym));
+    }
+
+    /// Flushes the final state and returns the stream in decoder order.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.out.extend_from_slice(&self.state.to_be_bytes());
+        self.out.reverse();
+        self.out
+    }
+}
+
+/// rANS decoder reading a stream produced by [`RansEncoder::finish`].
+pub struct RansDecoder<'a> {
+    state: u32,
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> RansDecoder<'a> {
+    /// Reads the initial state from the first four bytes of `buf`.
+    pub fn new(buf: &'a [u8]) -> Result<Self> {
+        let [state] = read_states::<1>(buf)?;
+        Ok(Self { state, buf, pos: 4 })
+    }
+
+    /// Current slot in `[0, PROB_SCALE)`.
+    #[inline]
+    pub fn peek(&self) -> u32 {
+        self.state & (PROB_SCALE - 1)
+    }
+
+    /// Consumes the symbol occupying `[start, start + freq)`.
+    #[inline]
+    pub fn advance(&mut self, start: u32, freq: u32) -> Result<()> {
+        advance_state(&mut self.state, self.buf, &mut self.pos, start, freq)
+    }
+
+    /// Decodes one symbol using `table`.
+    #[inline]
+    pub fn get_symbol(&mut self, table: &FreqTable) -> Result<usize> {
+        let sym = table.symbol_at(self.peek());
+        self.advance(table.cum(sym), table.freq(sym))?;
+        Ok(sym)
+    }
+
+    /// Returns `true` if the decoder is back in the encoder's initial state
+    /// with every byte consumed, i.e. the stream was decoded exactly.
+    pub fn is_finished(&self) -> bool {
+        self.state == RANS_L && self.pos == self.buf.len()
+    }
+}
+
+/// `N`-way interleaved rANS encoder sharing one byte stream.
+///
+/// Symbol `i` is coded by state `i % N`; the independent states let the
+/// decoder overlap the latency of `N` symbol lookups. The byte order depends
+/// only on integer arithmetic, so streams are identical on every target.
+/// Symbols must be fed in *reverse* order.
+pub struct InterleavedEncoder<const N: usize> {
+    states: [u32; N],
+    out: Vec<u8>,
+}
+
+impl<const N: usize> Default for InterleavedEncoder<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const N: usize> InterleavedEncoder<N> {
+    /// Creates an encoder with every state at its initial value.
+    pub fn new() -> Self {
+        Self { states: [RANS_L; N], out: Vec::new() }
+    }
+
+    /// Encodes one symbol occupying `[start, start + freq)` on state `lane`.
+    #[inline]
+    pub fn put(&mut self, lane: usize, start: u32, freq: u32) {
+        put_state(&mut self.states[lane], &mut self.out, start, freq);
+    }
+
+    /// Encodes `sym` on state `lane` using `table`.
+    #[inline]
+    pub fn put_symbol(&mut self, lane: usize, table: &FreqTable, sym: usize) {
+        self.put(lane, table.cum(sym), table.freq(sym));
+    }
+
+    /// Encodes `symbols` (in forward order) and returns the finished stream.
+    pub fn encode(mut self, table: &FreqTable, symbols: &[u8]) -> Vec<u8> {
+        for (i, &s) in symbols.iter().enumerate().rev() {
+            self.put_symbol(i % N, table, s as usize);
+        }
+        self.finish()
+    }
+
+    /// Flushes all states (lane 0 first in decoder order) and returns the stream.
+    pub fn finish(mut self) -> Vec<u8> {
+        for state in self.states.iter().rev() {
+            self.out.extend_from_slice(&state.to_be_bytes());
+        }
+        self.out.reverse();
+        self.out
+    }
+}
+
+/// Decoder for streams produced by [`InterleavedEncoder`].
+pub struct InterleavedDecoder<'a, const N: usize> {
+    states: [u32; N],
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a, const N: usize> InterleavedDecoder<'a, N> {
+    /// Reads the `N` initial states from the front of `buf`.
+    pub fn new(buf: &'a [u8]) -> Result<Self> {
+        Ok(Self { states: read_states::<N>(buf)?, buf, pos: 4 * N })
+    }
+
+    /// Decodes one symbol from state `lane` using `table`.
+    #[inline]
+    pub fn get_symbol(&mut self, lane: usize, table: &FreqTable) -> Result<usize> {
+        let state = &mut self.states[lane];
+        let sym = table.symbol_at(*state & (PROB_SCALE - 1));
+        advance_state(state, self.buf, &mut self.pos, table.cum(sym), table.freq(sym))?;
+        Ok(sym)
+    }
+
+    /// Decodes `len` byte symbols, appending them to `out`.
+    ///
+    /// Eight-lane streams over a byte alphabet use the AVX2 kernel when
+    /// `kernel` allows it; the output is identical either way.
+    #[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+    pub fn decode(&mut self, table: &FreqTable, len: usize, out: &mut Vec<u8>, kernel: Kernel) -> Result<()> {
+        let start = out.len();
+        out.resize(start + len, 0);
+        let result = self.decode_to_slice(table, &mut out[start..], kernel);
+        if result.is_err() {
+            out.truncate(start);
+        }
+        result
+    }
+
+    /// [`InterleavedDecoder::decode`] of `out.len()` symbols straight into `out`.
+    #[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+    pub(crate) fn decode_to_slice(&mut self, table: &FreqTable, out: &mut [u8], kernel: Kernel) -> Result<()> {
+        let split = out.len() - out.len() % N;
+        let (groups, tail) = out.split_at_mut(split);
+        #[cfg(target_arch = "x86_64")]
+        let groups = if N == 8 && table.len() == 256 && kernel.use_avx2() {
+            let states: &mut [u32; 8] = (&mut self.states[..]).try_into().unwrap();
+            // SAFETY: AVX2 support was verified at runtime and the table has 256 symbols.
+            unsafe { crate::simd::avx2::decode8(states, self.buf, &mut self.pos, table, groups)? };
+            &mut groups[..0]
+        } else {
+            groups
+        };
+        for group in groups.chunks_exact_mut(N) {
+            for (lane, o) in group.iter_mut().enumerate() {
+                *o = self.get_symbol(lane, table)? as u8;
+            }
+        }
+        for (lane, o) in tail.iter_mut().enumerate() {
+            *o = self.get_symbol(lane, table)? as u8;
+        }
+        Ok(())
+    }
+
+    /// Returns `true` if every state is back at its initial value with all
+    /// bytes consumed.
+    pub fn is_finished(&self) -> bool {
+        self.states.iter().all(|&s| s == RANS_L) && self.pos == self.buf.len()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_counts_normalizes() {
+        let mut counts = vec![0u32; 256];
+        counts[0] = 1_000_000;
+        counts[1] = 1;
+        counts[255] = 3;
+        let table = FreqTable::from_counts(&counts).unwrap();
+        assert_eq!(table.freqs().iter().sum::<u32>(), PROB_SCALE);
+        assert!(table.freq(1) >= 1 && table.freq(255) >= 1);
+        assert_eq!(table.freq(2), 0);
+    }
+
+    #[test]
+    fn single_symbol_takes_whole_range() {
+        let table = FreqTable::from_counts(&[0, 7]).unwrap();
+        assert_eq!(table.freq(1), PROB_SCALE);
+        assert_eq!(table.symbol_at(PROB_SCALE - 1), 1);
+    }
+}
@@ -0,0 +1,224 @@
+//! Runtime-dispatched kernels (design doc §4).
+//!
+//! The AVX2 kernels evaluate exactly the same IEEE-754 and integer operations,
+//! in the same order, as their scalar counterparts, so the selected kernel
+//! never changes the produced bytes; only the speed differs.
+
+/// Implementation used for the vectorizable hot loops.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Kernel {
+    /// AVX2 when the CPU supports it, scalar otherwise.
+    #[default]
+    Auto,
+    /// Portable scalar code.
+    Scalar,
+    /// x86_64 AVX2; falls back to scalar when unavailable.
+    Avx2,
+}
+
+impl Kernel {
+    /// Returns `true` if the AVX2 kernels can run on this CPU.
+    pub fn avx2_available() -> bool {
+        #[cfg(target_arch = "x86_64")]
+        {
+            std::arch::is_x86_feature_detected!("avx2")
+        }
+        #[cfg(not(target_arch = "x86_64"))]
+        {
+            false
+        }
+    }
+
+    /// Returns `true` if this choice runs the AVX2 kernels.
+    pub(crate) fn use_avx2(self) -> bool {
+        match self {
+            Kernel::Scalar => false,
+            Kernel::Auto | Kernel::Avx2 => Self::avx2_available(),
+        }
+    }
+}
+
+/// Unnormalized Gaussian weights `exp(-½((lo + j - μ)/σ)²)` for `j in 0..out.len()`.
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn gaussian_weights(kernel: Kernel, lo: u32, mu: f64, sigma: f64, out: &mut [f64]) {
+    #[cfg(target_arch = "x86_64")]
+    if kernel.use_avx2() {
+        // SAFETY: AVX2 support was verified at runtime by `use_avx2`.
+        unsafe { avx2::gaussian_weights(lo, mu, sigma, out) };
+        return;
+    }
+    for (j, w) in out.iter_mut().enumerate() {
+        let z = ((lo as usize + j) as f64 - mu) / sigma;
+        *w = exp_neg(0.5 * z * z);
+    }
+}
+
+/// Quantizes weights to `1 + ⌊w / total · spare⌋`.
+#[cfg_attr(not(target_arch = "x86_64"), allow(unused_variables))]
+pub(crate) fn quantize(kernel: Kernel, weights: &[f64], total: f64, spare: u32, out: &mut [u32]) {
+    #[cfg(target_arch = "x86_64")]
+    if kernel.use_avx2() {
+        // SAFETY: AVX2 support was verified at runtime by `use_avx2`.
+        unsafe { avx2::quantize(weights, total, spare, out) };
+        return;
+    }
+    for (f, &w) in out.iter_mut().zip(weights) {
+        *f = 1 + (w / total * spare as f64) as u32;
+    }
+}
+
+const EXP_CUTOFF: f64 = 700.0;
+const EXP_TERMS: u32 = 20;
+
+/// `exp(-x)` for `x >= 0` using only IEEE-754 basic operations.
+///
+/// Range reduction `x = k·ln2 + r` with `r ∈ [0, ln2)`, then a fixed-length
+/// Taylor series for `exp(-r)`. Results are bit-identical across targets.
+pub(crate) fn exp_neg(x: f64) -> f64 {
+    if x >= EXP_CUTOFF {
+        return 0.0;
+    }
+    let k = (x * std::f64::consts::LOG2_E) as i32;
+    let r = x - k as f64 * std::f64::consts::LN_2;
+    let mut term = 1.0;
+    let mut sum = 1.0;
+    for n in 1..=EXP_TERMS {
+        term *= -r / n as f64;
+        sum += term;
+    }
+    // 2^-k, exact for k < 1023.
+    sum * f64::from_bits(((1023 - k) as u64) << 52)
+}
+
+#[cfg(target_arch = "x86_64")]
+pub(crate) mod avx2 {
+    use super::{EXP_CUTOFF, EXP_TERMS};
+    use crate::error::{AnsxError, Result};
+    use crate::rans::{FreqTable, PROB_BITS, PROB_SCALE, RANS_L};
+    use std::arch::x86_64::*;
+
+    /// Four-wide [`super::exp_neg`].
+    #[target_feature(enable = "avx2")]
+    fn exp_neg4(x: __m256d) -> __m256d {
+        let k = _mm256_cvttpd_epi32(_mm256_mul_pd(x, _mm256_set1_pd(std::f64::consts::LOG2_E)));
+        let r = _mm256_sub_pd(x, _mm256_mul_pd(_mm256_cvtepi32_pd(k), _mm256_set1_pd(std::f64::consts::LN_2)));
+        let neg_r = _mm256_xor_pd(r, _mm256_set1_pd(-0.0));
+        let mut term = _mm256_set1_pd(1.0);
+        let mut sum = term;
+        for n in 1..=EXP_TERMS {
+            term = _mm256_mul_pd(term, _mm256_div_pd(neg_r, _mm256_set1_pd(n as f64)));
+            sum = _mm256_add_pd(sum, term);
+        }
+        let exp_bits = _mm256_slli_epi64::<52>(_mm256_sub_epi64(_mm256_set1_epi64x(1023), _mm256_cvtepi32_epi64(k)));
+        let scaled = _mm256_mul_pd(sum, _mm256_castsi256_pd(exp_bits));
+        let cut = _mm256_cmp_pd::<_CMP_GE_OQ>(x, _mm256_set1_pd(EXP_CUTOFF));
+        _mm256_andnot_pd(cut, scaled)
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn gaussian_weights(lo: u32, mu: f64, sigma: f64, out: &mut [f64]) {
+        let mu4 = _mm256_set1_pd(mu);
+        let sigma4 = _mm256_set1_pd(sigma);
+        let half = _mm256_set1_pd(0.5);
+        let mut chunks = out.chunks_exact_mut(4);
+        let mut base = lo as f64;
+        for chunk in &mut chunks {
+            let i = _mm256_add_pd(_mm256_set1_pd(base), _mm256_setr_pd(0.0, 1.0, 2.0, 3.0));
+            let z = _mm256_div_pd(_mm256_sub_pd(i, mu4), sigma4);
+            let w = exp_neg4(_mm256_mul_pd(_mm256_mul_pd(half, z), z));
+            // SAFETY: `chunk` is exactly four writable f64s.
+            unsafe { _mm256_storeu_pd(chunk.as_mut_ptr(), w) };
+            base += 4.0;
+        }
+        for (j, w) in chunks.into_remainder().iter_mut().enumerate() {
+            let z = (base + j as f64 - mu) / sigma;
+            *w = super::exp_neg(0.5 * z * z);
+        }
+    }
+
+    /// # Safety
+    /// The CPU must support AVX2.
+    #[target_feature(enable = "avx2")]
+    pub(super) unsafe fn quantize(weights: &[f64], total: f64, spare: u32, out: &mut [u32]) {
+        let total4 = _mm256_set1_pd(total);
+        let spare4 = _mm256_set1_pd(spare as f64);
+        let one = _mm_set1_epi32(1);
+        let mut outs = out.chunks_exact_mut(4);
+        let mut ws = weights.chunks_exact(4);
+        for (o, w) in (&mut outs).zip(&mut ws) {
+            // SAFETY: `w` is exactly four readable f64s.
+            let w = unsafe { _mm256_loadu_pd(w.as_ptr()) };
+            let f = _mm_add_epi32(one, _mm256_cvttpd_epi32(_mm256_mul_pd(_mm256_div_pd(w, total4), spare4)));
+            // SAFETY: `o` is exactly four writable u32s.
+            unsafe { _mm_storeu_si128(o.as_mut_ptr().cast(), f) };
+        }
+        for (f, &w) in outs.into_remainder().iter_mut().zip(ws.remainder()) {
+            *f = 1 + (w / total * spare as f64) as u32;
+        }
+    }
+
+    /// Decodes `out.len() / 8` rounds of eight interleaved byte symbols into `out`.
+    ///
+    /// Symbol lookup (a branchless binary search over the cumulative table)
+    /// and the state update run across all eight lanes at once; the
+    /// renormalization reads then happen lane by lane in stream order.
+    ///
+    /// # Safety
+    /// The CPU must support AVX2 and `table` must have exactly 256 symbols.
+    #[target_feature(enable = "avx2")]
+    pub(crate) unsafe fn decode8(
+        states: &mut [u32; 8],
+        buf: &[u8],
+        pos: &mut usize,
+        table: &FreqTable,
+        out: &mut [u8],
+    ) -> Result<()> {
+        debug_assert_eq!(table.len(), 256);
+        let cum = table.cum_table().as_ptr() as *const i32;
+        let freq = table.freqs().as_ptr() as *const i32;
+        let slot_mask = _mm256_set1_epi32((PROB_SCALE - 1) as i32);
+        // SAFETY: `states` is eight readable u32s.
+        let mut x = unsafe { _mm256_loadu_si256(states.as_ptr().cast()) };
+        let mut lanes = [0u32; 8];
+        for group in out.chunks_exact_mut(8) {
+            let slot = _mm256_and_si256(x, slot_mask);
+            let mut sym = _mm256_setzero_si256();
+            for step in [128, 64, 32, 16, 8, 4, 2, 1] {
+                let cand = _mm256_add_epi32(sym, _mm256_set1_epi32(step));
+                // SAFETY: `cand <= 255` and `cum` has 257 entries.
+                let c = unsafe { _mm256_i32gather_epi32::<4>(cum, cand) };
+                sym = _mm256_blendv_epi8(cand, sym, _mm256_cmpgt_epi32(c, slot));
+            }
+            // SAFETY: `sym <= 255` indexes both 256-entry frequency and 257-entry cumulative tables.
+            let (start, f) = unsafe { (_mm256_i32gather_epi32::<4>(cum, sym), _mm256_i32gather_epi32::<4>(freq, sym)) };
+            let next = _mm256_add_epi32(
+                _mm256_mullo_epi32(f, _mm256_srli_epi32::<{ PROB_BITS as i32 }>(x)),
+                _mm256_sub_epi32(slot, start),
+            );
+
+            let mut syms = [0u32; 8];
+            // SAFETY: both arrays are eight writable u32s.
+            unsafe {
+                _mm256_storeu_si256(syms.as_mut_ptr().cast(), sym);
+                _mm256_storeu_si256(lanes.as_mut_ptr().cast(), next);
+            }
+            for (o, &s) in group.iter_mut().zip(&syms) {
+                *o = s as u8;
+            }
+            for lane in &mut lanes {
+                while *lane < RANS_L {
+                    let b = *buf.get(*pos).ok_or(AnsxError::Truncated)?;
+                    *pos += 1;
+                    *lane = (*lane << 8) | b as u32;
+                }
+            }
+            // SAFETY: `lanes` is eight readable u32s.
+            x = unsafe { _mm256_loadu_si256(lanes.as_ptr().cast()) };
+        }
+        // SAFETY: `states` is eight writable u32s.
+        unsafe { _mm256_storeu_si256(states.as_mut_ptr().cast(), x) };
+        Ok(())
+    }
+}
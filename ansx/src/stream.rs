@@ -0,0 +1,236 @@
+//! Streaming encoder and decoder for inputs larger than memory.
+//!
+//! [`Encoder`] collects input into [`DEFAULT_BLOCK_SIZE`] blocks and writes
+//! each block's ANX1 chunk as soon as the block fills; [`Decoder`] reads and
+//! rebuilds one chunk at a time. Memory use is a few blocks whatever the
+//! stream length. Unless [`Write::flush`] is called part way, the encoder's
+//! output is byte-identical to [`crate::encode_with`].
+
+use crate::chunk::{Chunk, ChunkHeader, DEFAULT_BLOCK_SIZE, HEADER_LEN};
+use crate::codec::{self, DecodeOptions, EncodeOptions};
+use crate::error::{AnsxError, Result};
+use std::io::{self, Read, Write};
+
+const BLOCK: usize = DEFAULT_BLOCK_SIZE as usize;
+
+/// Incremental encoder writing ANX1 chunks to `W`.
+///
+/// Call [`Encoder::finish`] to write the final partial block; dropping the
+/// encoder discards it.
+#[derive(Debug)]
+pub struct Encoder<W: Write> {
+    inner: W,
+    options: EncodeOptions,
+    block: Vec<u8>,
+    // Previous block for repeat skipping; empty before the first block.
+    prev: Vec<u8>,
+    payload: Vec<u8>,
+    out: Vec<u8>,
+}
+
+impl<W: Write> Encoder<W> {
+    /// Creates an encoder with default settings.
+    pub fn new(inner: W) -> Self {
+        Self::with_options(inner, EncodeOptions::new())
+    }
+
+    /// Creates an encoder with explicit settings.
+    pub fn with_options(inner: W, options: EncodeOptions) -> Self {
+        Self {
+            inner,
+            options,
+            block: Vec::with_capacity(BLOCK),
+            prev: Vec::with_capacity(BLOCK),
+            payload: Vec::new(),
+            out: Vec::new(),
+        }
+    }
+
+    /// Underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Mutable access to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes the buffered partial block and returns the underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.emit()?;
+        Ok(self.inner)
+    }
+
+    /// Writes the buffered block, if any, as one chunk.
+    fn emit(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        self.out.clear();
+        let prev = (!self.prev.is_empty()).then_some(&self.prev[..]);
+        codec::encode_block(&self.block, prev, &self.options, &mut self.payload, &mut self.out);
+        self.inner.write_all(&self.out)?;
+        std::mem::swap(&mut self.prev, &mut self.block);
+        self.block.clear();
+        Ok(())
+    }
+}
+
+impl<W: Write> Write for Encoder<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let take = (BLOCK - self.block.len()).min(buf.len());
+        self.block.extend_from_slice(&buf[..take]);
+        if self.block.len() == BLOCK {
+            self.emit()?;
+        }
+        Ok(take)
+    }
+
+    /// Ends the current block early so everything written so far can be
+    /// decoded, then flushes the underlying writer.
+    fn flush(&mut self) -> io::Result<()> {
+        self.emit()?;
+        self.inner.flush()
+    }
+}
+
+/// Rebuilds blocks one chunk at a time and hands out their bytes.
+#[derive(Debug)]
+pub(crate) struct BlockReader {
+    options: DecodeOptions,
+    // Current block; the previous block until the next chunk is decoded.
+    block: Vec<u8>,
+    pos: usize,
+    produced: usize,
+}
+
+impl BlockReader {
+    pub(crate) fn new(options: DecodeOptions) -> Self {
+        Self { options, block: Vec::with_capacity(BLOCK), pos: 0, produced: 0 }
+    }
+
+    /// Returns `true` once every byte of the current block has been read.
+    pub(crate) fn is_drained(&self) -> bool {
+        self.pos == self.block.len()
+    }
+
+    /// Replaces the drained current block with the one `chunk` encodes.
+    pub(crate) fn push(&mut self, chunk: &Chunk<'_>) -> Result<()> {
+        debug_assert!(self.is_drained());
+        let len = chunk.header.block_size as usize;
+        if self.produced.saturating_add(len) > self.options.max_output {
+            return Err(AnsxError::OutputTooLarge);
+        }
+        let prev_len = self.block.len();
+        let prev = (prev_len > 0).then_some(0..prev_len);
+        if let Err(e) = codec::decode_block(chunk, prev, self.options.kernel, &mut self.block) {
+            self.block.truncate(prev_len);
+            return Err(e);
+        }
+        self.block.drain(..prev_len);
+        self.pos = 0;
+        self.produced += len;
+        Ok(())
+    }
+
+    /// Copies unread bytes of the current block into `buf`.
+    pub(crate) fn read_into(&mut self, buf: &mut [u8]) -> usize {
+        let n = (self.block.len() - self.pos).min(buf.len());
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        n
+    }
+}
+
+/// Incremental decoder reading ANX1 chunks from `R`.
+///
+/// Decoding errors surface as [`io::Error`]s wrapping [`AnsxError`]
+/// (`UnexpectedEof` for truncation, `InvalidData` otherwise), and every later
+/// read repeats the first error.
+#[derive(Debug)]
+pub struct Decoder<R: Read> {
+    inner: R,
+    reader: BlockReader,
+    chunk: Vec<u8>,
+    error: Option<AnsxError>,
+}
+
+impl<R: Read> Decoder<R> {
+    /// Creates a decoder with default settings.
+    pub fn new(inner: R) -> Self {
+        Self::with_options(inner, DecodeOptions::new())
+    }
+
+    /// Creates a decoder with explicit settings. The output limit applies to
+    /// the whole stream.
+    pub fn with_options(inner: R, options: DecodeOptions) -> Self {
+        Self { inner, reader: BlockReader::new(options), chunk: Vec::new(), error: None }
+    }
+
+    /// Underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads and decodes the next chunk. Returns `false` at a clean end of input.
+    fn next_chunk(&mut self) -> io::Result<bool> {
+        self.chunk.resize(HEADER_LEN, 0);
+        let got = read_full(&mut self.inner, &mut self.chunk)?;
+        if got == 0 {
+            return Ok(false);
+        }
+        if got < HEADER_LEN {
+            return Err(self.fail(AnsxError::Truncated));
+        }
+        let header = ChunkHeader::parse(&self.chunk).map_err(|e| self.fail(e.into()))?;
+        let want = header.cmpr_len as u64;
+        if (&mut self.inner).take(want).read_to_end(&mut self.chunk)? as u64 != want {
+            return Err(self.fail(AnsxError::Truncated));
+        }
+        let result = Chunk::parse(&self.chunk).map_err(AnsxError::from).and_then(|(c, _)| self.reader.push(&c));
+        result.map_err(|e| self.fail(e))?;
+        Ok(true)
+    }
+
+    fn fail(&mut self, e: AnsxError) -> io::Error {
+        self.error = Some(e);
+        e.into()
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(e) = self.error {
+            return Err(e.into());
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.reader.is_drained() {
+            if !self.next_chunk()? {
+                return Ok(0);
+            }
+        }
+        Ok(self.reader.read_into(buf))
+    }
+}
+
+/// Fills `buf` from `r` unless it ends first; returns the bytes read.
+fn read_full(r: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut got = 0;
+    while got < buf.len() {
+        match r.read(&mut buf[got..]) {
+            Ok(0) => break,
+            Ok(n) => got += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(got)
+}
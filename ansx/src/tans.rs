@@ -0,0 +1,331 @@
+//! Table-driven ANS (tANS/FSE) over the byte alphabet.
+//!
+//! Decoding is one table lookup and one bit-field read per symbol, with no
+//! multiply or division, which suits small alphabets such as `ledgerizer` run
+//! values. Frequencies are normalized to `1 << log` and spread over a state
+//! table of that size; each state records its symbol, how many bits to read
+//! and the base of the next state.
+//!
+//! A stream is MSB-first (see [`crate::bitio`]): the decoder's initial state
+//! in `log` bits, then the bits of every symbol in decoding order, padded with
+//! zero bits to a byte. The decoder must end in state 0, the encoder's start.
+//!
+//! Tables have their own serialization:
+//!
+//! ```text
+//! gamma(len)          alphabet size, 1..=256
+//! log: 4 bits         table log, MIN_TABLE_LOG..=MAX_TABLE_LOG
+//! per symbol:
+//!   gamma(freq + 1)
+//!   gamma(run + 1)    after a zero only: further zero-frequency symbols
+//! ```
+//!
+//! Entries stop once the frequencies reach `1 << log`.
+
+use crate::bitio::{BitReader, BitWriter};
+use crate::error::{AnsxError, Result};
+use crate::rans::normalize;
+
+/// Table log used by the encoder: 2048 states.
+pub const TABLE_LOG: u32 = 11;
+/// Smallest table log a serialized table may declare.
+pub const MIN_TABLE_LOG: u32 = 5;
+/// Largest table log a serialized table may declare.
+pub const MAX_TABLE_LOG: u32 = 12;
+
+const LOG_BITS: u32 = 4;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct DecodeEntry {
+    sym: u8,
+    bits: u8,
+    base: u16,
+}
+
+/// Normalized byte frequencies and the state tables built from them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TansTable {
+    log: u32,
+    freq: Vec<u32>,
+    cum: Vec<u32>,
+    decode: Vec<DecodeEntry>,
+    // Next encoder state for occurrence `k` of symbol `s`, at `cum[s] + k`.
+    encode: Vec<u16>,
+}
+
+impl TansTable {
+    /// Builds a `1 << log`-state table from raw byte counts. Returns `None` if
+    /// all counts are zero, there are more than 256, or `log` is out of range.
+    pub fn from_counts(counts: &[u32], log: u32) -> Option<Self> {
+        if counts.len() > 256 || !(MIN_TABLE_LOG..=MAX_TABLE_LOG).contains(&log) {
+            return None;
+        }
+        Self::from_freqs(normalize(counts, 1 << log)?, log)
+    }
+
+    /// Wraps already-normalized frequencies. Returns `None` unless there are
+    /// 1 to 256 of them summing to exactly `1 << log`.
+    pub fn from_freqs(freq: Vec<u32>, log: u32) -> Option<Self> {
+        if freq.is_empty() || freq.len() > 256 || !(MIN_TABLE_LOG..=MAX_TABLE_LOG).contains(&log) {
+            return None;
+        }
+        let size = 1usize << log;
+        let mut cum = Vec::with_capacity(freq.len() + 1);
+        let mut acc = 0u32;
+        cum.push(0);
+        for &f in &freq {
+            acc = acc.checked_add(f)?;
+            cum.push(acc);
+        }
+        if acc as usize != size {
+            return None;
+        }
+
+        // Spread each symbol's states across the table with an odd stride,
+        // which visits every slot once.
+        let mask = size - 1;
+        let step = (size >> 1) + (size >> 3) + 3;
+        let mut spread = vec![0u8; size];
+        let mut pos = 0;
+        for (s, &f) in freq.iter().enumerate() {
+            for _ in 0..f {
+                spread[pos] = s as u8;
+                pos = (pos + step) & mask;
+            }
+        }
+
+        let mut next: Vec<u32> = freq.clone();
+        let mut decode = Vec::with_capacity(size);
+        let mut encode = vec![0u16; size];
+        for (slot, &s) in spread.iter().enumerate() {
+            let x = next[s as usize];
+            next[s as usize] += 1;
+            let bits = log - (31 - x.leading_zeros());
+            decode.push(DecodeEntry { sym: s, bits: bits as u8, base: ((x << bits) - size as u32) as u16 });
+            let k = x - freq[s as usize];
+            encode[(cum[s as usize] + k) as usize] = slot as u16;
+        }
+        Some(Self { log, freq, cum, decode, encode })
+    }
+
+    /// Table log; the table has `1 << log` states.
+    pub fn log(&self) -> u32 {
+        self.log
+    }
+
+    /// Number of symbols in the alphabet.
+    pub fn len(&self) -> usize {
+        self.freq.len()
+    }
+
+    /// Returns `true` if the alphabet is empty, which a valid table never is.
+    pub fn is_empty(&self) -> bool {
+        self.freq.is_empty()
+    }
+
+    /// Normalized frequencies, summing to `1 << log`.
+    pub fn freqs(&self) -> &[u32] {
+        &self.freq
+    }
+}
+
+/// Appends `table` to `w`.
+pub fn write_table(table: &TansTable, w: &mut BitWriter) {
+    w.write_gamma(table.freq.len() as u32);
+    w.write_bits(table.log, LOG_BITS);
+    let mut i = 0;
+    while i < table.freq.len() && table.cum[i] < 1 << table.log {
+        let f = table.freq[i];
+        w.write_gamma(f + 1);
+        i += 1;
+        if f == 0 {
+            let run = table.freq[i..].iter().take_while(|&&f| f == 0).count();
+            w.write_gamma(run as u32 + 1);
+            i += run;
+        }
+    }
+}
+
+/// Reads a table written by [`write_table`].
+///
+/// Fails with [`AnsxError::BadHeader`] if the table is malformed or does not
+/// sum to `1 << log`, and [`AnsxError::Truncated`] if `r` runs out.
+pub fn read_table(r: &mut BitReader<'_>) -> Result<TansTable> {
+    let len = r.read_gamma()? as usize;
+    let log = r.read_bits(LOG_BITS)?;
+    if len > 256 || !(MIN_TABLE_LOG..=MAX_TABLE_LOG).contains(&log) {
+        return Err(AnsxError::BadHeader);
+    }
+    let size = 1u32 << log;
+    let mut freq = vec![0u32; len];
+    let mut sum = 0u32;
+    let mut sym = 0;
+    while sum < size {
+        let f = r.read_gamma()? - 1;
+        if f > size - sum {
+            return Err(AnsxError::BadHeader);
+        }
+        *freq.get_mut(sym).ok_or(AnsxError::BadHeader)? = f;
+        sum += f;
+        sym += 1;
+        if f == 0 {
+            sym += r.read_gamma()? as usize - 1;
+        }
+    }
+    TansTable::from_freqs(freq, log).ok_or(AnsxError::BadHeader)
+}
+
+/// Serializes `table` on its own, padded to whole bytes.
+pub fn encode_table(table: &TansTable) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    write_table(table, &mut w);
+    w.finish()
+}
+
+/// Parses a table from the front of `input`, returning it and the number of
+/// bytes it occupied.
+pub fn decode_table(input: &[u8]) -> Result<(TansTable, usize)> {
+    let mut r = BitReader::new(input);
+    let table = read_table(&mut r)?;
+    Ok((table, r.byte_pos()))
+}
+
+/// Encodes `symbols` with `table`.
+///
+/// # Panics
+/// If a symbol has frequency 0 in `table`.
+pub fn encode(table: &TansTable, symbols: &[u8]) -> Vec<u8> {
+    let log = table.log;
+    let size = 1u32 << log;
+    // Symbols are coded last to first, so keep each one's bits and write them
+    // out in reverse for the decoder to read forwards.
+    let mut fields: Vec<(u16, u8)> = Vec::with_capacity(symbols.len());
+    let mut x = size;
+    for &s in symbols.iter().rev() {
+        let f = table.freq[s as usize];
+        assert!(f > 0, "symbol {s} is not in the table");
+        let mut bits = log - (31 - f.leading_zeros());
+        if x >> bits < f {
+            bits -= 1;
+        }
+        fields.push(((x & ((1 << bits) - 1)) as u16, bits as u8));
+        let k = (x >> bits) - f;
+        x = size + table.encode[(table.cum[s as usize] + k) as usize] as u32;
+    }
+
+    let mut w = BitWriter::new();
+    w.write_bits(x - size, log);
+    for &(value, bits) in fields.iter().rev() {
+        w.write_bits(value as u32, bits as u32);
+    }
+    w.finish()
+}
+
+/// Decodes `len` symbols produced by [`encode`] with the same table, appending
+/// them to `out`.
+///
+/// Fails with [`AnsxError::Truncated`] if the stream ends early and
+/// [`AnsxError::Corrupt`] unless it is consumed exactly, ending in state 0
+/// with zero padding.
+pub fn decode(table: &TansTable, stream: &[u8], len: usize, out: &mut Vec<u8>) -> Result<()> {
+    let start = out.len();
+    out.resize(start + len, 0);
+    let result = decode_to_slice(table, stream, &mut out[start..]);
+    if result.is_err() {
+        out.truncate(start);
+    }
+    result
+}
+
+/// [`decode`] of `out.len()` symbols straight into `out`.
+pub(crate) fn decode_to_slice(table: &TansTable, stream: &[u8], out: &mut [u8]) -> Result<()> {
+    let mut bits = Bits::new(stream);
+    let mut state = bits.read(table.log) as usize;
+    for o in out {
+        let e = table.decode[state];
+        *o = e.sym;
+        state = e.base as usize + bits.read(e.bits as u32) as usize;
+    }
+    let used = bits.consumed();
+    if used > stream.len() * 8 {
+        return Err(AnsxError::Truncated);
+    }
+    let padding = (stream.len() * 8 - used) as u32;
+    if state != 0 || padding >= 8 || bits.read(padding) != 0 {
+        return Err(AnsxError::Corrupt);
+    }
+    Ok(())
+}
+
+/// MSB-first bit reader for the decode loop. Reads past the end yield zeros;
+/// [`Bits::consumed`] tells the caller whether that happened.
+struct Bits<'a> {
+    buf: &'a [u8],
+    pos: usize,
+    // Unread bits, left-aligned.
+    acc: u64,
+    avail: u32,
+}
+
+impl<'a> Bits<'a> {
+    fn new(buf: &'a [u8]) -> Self {
+        Self { buf, pos: 0, acc: 0, avail: 0 }
+    }
+
+    #[inline(always)]
+    fn refill(&mut self) {
+        if let Some(word) = self.buf.get(self.pos..self.pos + 8) {
+            // Bits below the claimed bytes are the true stream too, so the next
+            // refill ORs the same values back in.
+            self.acc |= u64::from_be_bytes(word.try_into().unwrap()) >> self.avail;
+            let take = (63 - self.avail) / 8;
+            self.pos += take as usize;
+            self.avail += take * 8;
+        } else {
+            while self.avail <= 56 {
+                let b = self.buf.get(self.pos).copied().unwrap_or(0);
+                self.acc |= (b as u64) << (56 - self.avail);
+                self.pos += 1;
+                self.avail += 8;
+            }
+        }
+    }
+
+    /// Reads `n <= MAX_TABLE_LOG` bits.
+    #[inline(always)]
+    fn read(&mut self, n: u32) -> u32 {
+        if self.avail < n {
+            self.refill();
+        }
+        let v = ((self.acc >> 1) >> (63 - n)) as u32;
+        self.acc <<= n;
+        self.avail -= n;
+        v
+    }
+
+    /// Bits read so far, including any zeros read past the end.
+    fn consumed(&self) -> usize {
+        self.pos * 8 - self.avail as usize
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn states_decode_back_to_the_encoder_slots() {
+        let table = TansTable::from_counts(&[5, 0, 1, 30, 2], 5).unwrap();
+        assert_eq!(table.freqs().iter().sum::<u32>(), 32);
+        for (slot, e) in table.decode.iter().enumerate() {
+            // Following the entry with its largest bit field must stay in range.
+            let top = e.base as u32 + (1 << e.bits) - 1;
+            assert!(top < 32, "slot {slot}");
+        }
+        let symbols = [3, 3, 0, 2, 3, 4, 3, 3, 0, 3];
+        let stream = encode(&table, &symbols);
+        let mut out = Vec::new();
+        decode(&table, &stream, symbols.len(), &mut out).unwrap();
+        assert_eq!(out, symbols);
+    }
+}
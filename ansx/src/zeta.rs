@@ -0,0 +1,111 @@
+//! ζ-coded frequency tables (docs/rbt_entropy_map.md, "Frequency Table
+//! Compression").
+//!
+//! Each used symbol's frequency is ζ-coded: `"0"` for a frequency of 1,
+//! otherwise `"1"` followed by `freq - 1` in a table-wide bit width, which
+//! keeps the code self-delimiting. Layout (see [`crate::bitio`]):
+//!
+//! ```text
+//! gamma(len)          alphabet size, 1..=MAX_ALPHABET
+//! sparse: 1 bit       1 if some symbols have frequency 0
+//! width: 5 bits       bit length of the largest freq - 1 (0..=16)
+//! per used symbol:
+//!   gamma(gap + 1)    sparse tables only: zero-frequency symbols skipped
+//!   ζ(freq)
+//! ```
+//!
+//! Entries stop once the frequencies reach `PROB_SCALE`; any symbols left
+//! over have frequency 0.
+
+use crate::bitio::{BitReader, BitWriter};
+use crate::error::{AnsxError, Result};
+use crate::rans::{FreqTable, PROB_SCALE};
+
+/// Largest alphabet a serialized table may declare.
+pub const MAX_ALPHABET: usize = PROB_SCALE as usize;
+
+const WIDTH_BITS: u32 = 5;
+
+fn bit_width(v: u32) -> u32 {
+    32 - v.leading_zeros()
+}
+
+/// Appends `table` to `w`.
+///
+/// # Panics
+/// If the alphabet is empty or larger than [`MAX_ALPHABET`].
+pub fn write_table(table: &FreqTable, w: &mut BitWriter) {
+    assert!((1..=MAX_ALPHABET).contains(&table.len()), "alphabet size out of range");
+    let freqs = table.freqs();
+    let sparse = freqs.contains(&0);
+    let width = bit_width(freqs.iter().max().map_or(0, |&f| f - 1));
+
+    w.write_gamma(freqs.len() as u32);
+    w.write_bit(sparse);
+    w.write_bits(width, WIDTH_BITS);
+    let mut gap = 0;
+    for &f in freqs {
+        if f == 0 {
+            gap += 1;
+            continue;
+        }
+        if sparse {
+            w.write_gamma(gap + 1);
+            gap = 0;
+        }
+        if f == 1 {
+            w.write_bit(false);
+        } else {
+            w.write_bit(true);
+            w.write_bits(f - 1, width);
+        }
+    }
+}
+
+/// Reads a table written by [`write_table`].
+///
+/// Fails with [`AnsxError::BadHeader`] if the table is malformed or does not
+/// sum to `PROB_SCALE`, and [`AnsxError::Truncated`] if `r` runs out.
+pub fn read_table(r: &mut BitReader<'_>) -> Result<FreqTable> {
+    let len = r.read_gamma()? as usize;
+    if len > MAX_ALPHABET {
+        return Err(AnsxError::BadHeader);
+    }
+    let sparse = r.read_bit()?;
+    let width = r.read_bits(WIDTH_BITS)?;
+    if width > 16 {
+        return Err(AnsxError::BadHeader);
+    }
+
+    let mut freq = vec![0u32; len];
+    let mut sum = 0;
+    let mut sym = 0;
+    while sum < PROB_SCALE {
+        if sparse {
+            sym += r.read_gamma()? as usize - 1;
+        }
+        let slot = freq.get_mut(sym).ok_or(AnsxError::BadHeader)?;
+        *slot = if r.read_bit()? { r.read_bits(width)? + 1 } else { 1 };
+        sum += *slot;
+        sym += 1;
+    }
+    if !sparse && sym != len {
+        return Err(AnsxError::BadHeader);
+    }
+    FreqTable::from_freqs(freq).ok_or(AnsxError::BadHeader)
+}
+
+/// Serializes `table` on its own, padded to whole bytes.
+pub fn encode_table(table: &FreqTable) -> Vec<u8> {
+    let mut w = BitWriter::new();
+    write_table(table, &mut w);
+    w.finish()
+}
+
+/// Parses a table from the front of `input`, returning it and the number of
+/// bytes it occupied.
+pub fn decode_table(input: &[u8]) -> Result<(FreqTable, usize)> {
+    let mut r = BitReader::new(input);
+    let table = read_table(&mut r)?;
+    Ok((table, r.byte_pos()))
+}
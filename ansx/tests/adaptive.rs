@@ -0,0 +1,82 @@
+use ansx::chunk::{self, FLAG_ADAPTIVE, FLAG_CHECKSUM};
+use ansx::{AnsxError, EncodeOptions, Interleave, Model, decode, encode_with};
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+fn skewed(len: usize, seed: u64) -> Vec<u8> {
+    random_bytes(len, seed).iter().map(|b| b.leading_zeros() as u8).collect()
+}
+
+/// Run-length style data whose alphabet shifts every few kilobytes.
+fn drifting(len: usize, seed: u64) -> Vec<u8> {
+    skewed(len, seed).iter().enumerate().map(|(i, &b)| b + (i / 4096) as u8 * 8).collect()
+}
+
+const MODES: [Interleave; 3] = [Interleave::Single, Interleave::Four, Interleave::Eight];
+
+fn adaptive(mode: Interleave) -> EncodeOptions {
+    EncodeOptions::new().model(Model::Adaptive).interleave(mode)
+}
+
+#[test]
+fn roundtrip_all_lane_counts() {
+    let inputs = [Vec::new(), vec![7], skewed(33, 1), drifting(70_001, 2), random_bytes(20_000, 3), vec![0x55; 40_000]];
+    for mode in MODES {
+        for src in &inputs {
+            let encoded = encode_with(src, &adaptive(mode));
+            assert_eq!(&decode(&encoded).unwrap(), src, "{mode:?}, {} bytes", src.len());
+        }
+    }
+}
+
+#[test]
+fn flag_marks_adaptive_chunks() {
+    let src = drifting(50_000, 4);
+    let encoded = encode_with(&src, &adaptive(Interleave::Four));
+    for c in chunk::chunks(&encoded) {
+        let header = c.unwrap().header;
+        assert_eq!(header.parity_flag, FLAG_ADAPTIVE | FLAG_CHECKSUM);
+        assert_eq!(header.lanes, 4);
+    }
+    let fixed = encode_with(&src, &EncodeOptions::new());
+    assert!(chunk::chunks(&fixed).all(|c| c.unwrap().header.parity_flag & FLAG_ADAPTIVE == 0));
+}
+
+#[test]
+fn incompressible_blocks_fall_back_to_stored() {
+    let src = random_bytes(16_384, 5);
+    let encoded = encode_with(&src, &adaptive(Interleave::Single));
+    let c = chunk::chunks(&encoded).next().unwrap().unwrap();
+    assert_eq!(c.header.parity_flag, FLAG_CHECKSUM);
+    assert_eq!(decode(&encoded).unwrap(), src);
+}
+
+#[test]
+fn beats_static_tables_on_small_and_drifting_blocks() {
+    for src in [skewed(300, 6).iter().map(|b| b * 29).collect(), drifting(16_384, 7)] {
+        let fixed = encode_with(&src, &EncodeOptions::new()).len();
+        let learned = encode_with(&src, &adaptive(Interleave::Single)).len();
+        assert!(learned < fixed, "{} bytes: adaptive {learned} vs static {fixed}", src.len());
+    }
+}
+
+#[test]
+fn detects_truncated_stream() {
+    let src = drifting(10_000, 8);
+    let mut encoded = encode_with(&src, &adaptive(Interleave::Single));
+    // Drop the last payload byte and patch both length fields to match.
+    encoded.pop();
+    let cmpr_len = u32::from_le_bytes(encoded[10..14].try_into().unwrap()) - 1;
+    encoded[4..8].copy_from_slice(&(cmpr_len + 10).to_le_bytes());
+    encoded[10..14].copy_from_slice(&cmpr_len.to_le_bytes());
+    assert!(matches!(decode(&encoded), Err(AnsxError::Truncated | AnsxError::Corrupt)));
+}
@@ -0,0 +1,72 @@
+use ansx::codec::Skip;
+use ansx::*;
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+/// Bell-shaped bytes around 128: the sum of eight uniform nibbles.
+fn bell(len: usize, seed: u64) -> Vec<u8> {
+    random_bytes(len * 8, seed).chunks(8).map(|c| c.iter().map(|&b| b >> 4).sum::<u8>() + 68).collect()
+}
+
+fn payload_bits(data: &[u8], options: &EncodeOptions) -> f64 {
+    (encode_with(data, &options.checksum(false)).len() - chunk::HEADER_LEN - 1) as f64 * 8.0
+}
+
+#[test]
+fn estimates_track_encoded_sizes() {
+    let skewed: Vec<u8> = random_bytes(16_384, 1).iter().map(|b| b.leading_zeros() as u8 * 3).collect();
+    let report = analyze(&skewed);
+    assert_eq!(report.len(), 1);
+    let r = &report[0];
+
+    let static_actual = payload_bits(&skewed, &EncodeOptions::new());
+    assert!((static_actual - r.static_bits()).abs() < 0.01 * static_actual + 64.0, "{static_actual}");
+    let adaptive_actual = payload_bits(&skewed, &EncodeOptions::new().model(Model::Adaptive));
+    assert!((adaptive_actual - r.adaptive_bits).abs() < 0.01 * adaptive_actual + 64.0, "{adaptive_actual}");
+    assert!(r.entropy_bits <= r.adaptive_bits);
+    assert!(r.entropy_bits <= r.gaussian_bits);
+    assert_eq!(r.skip, None);
+}
+
+#[test]
+fn gaussian_fits_bell_shaped_data() {
+    let data = bell(16_384, 2);
+    let r = analyze(&data)[0];
+    assert!((r.gaussian.mu - 128.0).abs() < 2.0, "{:?}", r.gaussian);
+    assert!(r.gaussian_bits < r.static_bits(), "{r:?}");
+    assert!(r.gaussian_bits < r.entropy_bits * 1.05, "{r:?}");
+
+    // Skewed data is far from Gaussian.
+    let skewed: Vec<u8> = random_bytes(16_384, 3).iter().map(|b| b.leading_zeros() as u8).collect();
+    let r = analyze(&skewed)[0];
+    assert!(r.gaussian_bits > r.entropy_bits * 1.1, "{r:?}");
+}
+
+#[test]
+fn reports_skips_per_block() {
+    let block = random_bytes(16_384, 4);
+    let mut data = block.clone();
+    data.extend(&block);
+    data.extend([0; 16_384]);
+    data.extend([9; 100]);
+    let report = analyze(&data);
+    let skips: Vec<Option<Skip>> = report.iter().map(|r| r.skip).collect();
+    assert_eq!(skips, [None, Some(Skip::Repeat), Some(Skip::Zeros), None]);
+    let offsets: Vec<usize> = report.iter().map(|r| r.offset).collect();
+    assert_eq!(offsets, [0, 16_384, 32_768, 49_152]);
+
+    let constant = report[3];
+    assert_eq!(constant.len, 100);
+    assert_eq!(constant.entropy_bits, 0.0);
+    assert_eq!(constant.gaussian.sigma, analyze::MIN_SIGMA);
+    assert!(analyze(&[]).is_empty());
+}
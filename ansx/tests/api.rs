@@ -1,16 +1,156 @@
-use ansx::*;
+use ansx::chunk::{Chunk, FLAG_CHECKSUM, FLAG_PARITY, MAX_BLOCK_SIZE};
+use ansx::{
+    AnsxError, DecodeOptions, EncodeOptions, Interleave, Model, decode, decode_into, decode_with, encode, encode_into,
+    encode_with, max_encoded_len,
+};
+
+/// Deterministic xorshift bytes so the tests need no RNG dependency.
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
 
 #[test]
-fn roundtrip_identity() {
+fn roundtrip_small_text() {
     let src = b"hello world";
-    let mut len: u32 = 0;
-    unsafe {
-        let ptr = ansx_encode(src.as_ptr(), src.len() as _, &mut len as *mut _);
-        // encode allocates new buffer, ensure len matches
-        assert_eq!(len as usize, src.len());
-        let slice = std::slice::from_raw_parts(ptr, len as usize);
-        assert_eq!(&slice[..], src);
-        // free
-        ansx_free(ptr.cast(), len);
+    assert_eq!(decode(&encode(src)).unwrap(), src);
+}
+
+#[test]
+fn roundtrip_random_64kb() {
+    let src = random_bytes(65536, 0x9E37_79B9_7F4A_7C15);
+    let encoded = encode(&src);
+    assert_eq!(decode(&encoded).unwrap(), src);
+    // Incompressible data falls back to the stored method: chunk header,
+    // method byte and CRC-32 per 16 KB block.
+    assert!(encoded.len() <= src.len() + 4 * 23);
+}
+
+#[test]
+fn roundtrip_constant() {
+    let src = vec![0xABu8; 100_000];
+    let encoded = encode(&src);
+    assert_eq!(decode(&encoded).unwrap(), src);
+    assert!(encoded.len() < src.len() / 500, "constant input should collapse, got {} bytes", encoded.len());
+}
+
+#[test]
+fn roundtrip_empty() {
+    assert!(decode(&encode(&[])).unwrap().is_empty());
+}
+
+#[test]
+fn roundtrip_skewed_compresses() {
+    let src: Vec<u8> = random_bytes(50_000, 7).iter().map(|b| b % 4).collect();
+    let encoded = encode(&src);
+    assert_eq!(decode(&encoded).unwrap(), src);
+    // Four equiprobable symbols cost two bits each.
+    assert!(encoded.len() < src.len() / 3);
+}
+
+#[test]
+fn decode_reports_truncation() {
+    let encoded = encode(b"abracadabra abracadabra abracadabra");
+    assert_eq!(decode(&encoded[..3]), Err(AnsxError::Truncated));
+    assert_eq!(decode(&encoded[..encoded.len() - 1]), Err(AnsxError::Truncated));
+}
+
+#[test]
+fn decode_reports_bad_header() {
+    let mut encoded = encode(b"abracadabra");
+    encoded[0] = 0x7F;
+    assert_eq!(decode(&encoded), Err(AnsxError::BadHeader));
+}
+
+#[test]
+fn decode_reports_corruption() {
+    // Re-frame the payload with a stray trailing byte the rANS decoder never consumes.
+    let encoded = encode(b"abracadabra abracadabra abracadabra");
+    let (chunk, _) = Chunk::parse(&encoded).unwrap();
+    let payload = [chunk.data, &[0]].concat();
+    let mut tampered = Vec::new();
+    Chunk::new(chunk.header.block_size, 0, &payload).unwrap().write(&mut tampered);
+    assert_eq!(decode(&tampered), Err(AnsxError::Corrupt));
+}
+
+#[test]
+fn decode_reports_checksum_mismatch() {
+    // Stored blocks decode any bytes, so only the CRC catches the flip.
+    let src = random_bytes(1000, 11);
+    let mut encoded = encode(&src);
+    encoded[100] ^= 0x20;
+    assert_eq!(decode(&encoded), Err(AnsxError::Checksum));
+}
+
+#[test]
+fn checksum_can_be_disabled() {
+    let src = random_bytes(1000, 12);
+    let with = encode(&src);
+    let without = encode_with(&src, &EncodeOptions::new().checksum(false));
+    assert_eq!(Chunk::parse(&with).unwrap().0.header.parity_flag, FLAG_CHECKSUM);
+    assert_eq!(Chunk::parse(&without).unwrap().0.header.parity_flag, 0);
+    assert_eq!(with.len(), without.len() + 4);
+    assert_eq!(decode(&without).unwrap(), src);
+}
+
+#[test]
+fn decode_enforces_output_limit() {
+    let src = random_bytes(40_000, 13);
+    let encoded = encode(&src);
+    let exact = DecodeOptions::new().max_output(src.len());
+    assert_eq!(decode_with(&encoded, &exact).unwrap(), src);
+    let short = DecodeOptions::new().max_output(src.len() - 1);
+    assert_eq!(decode_with(&encoded, &short), Err(AnsxError::OutputTooLarge));
+
+    // The limit is checked from the headers, before any block is expanded.
+    let mut zeros = Vec::new();
+    for _ in 0..1000 {
+        Chunk::new(MAX_BLOCK_SIZE, FLAG_PARITY, &[]).unwrap().write(&mut zeros);
+    }
+    let limit = DecodeOptions::new().max_output(1 << 20);
+    assert_eq!(decode_with(&zeros, &limit), Err(AnsxError::OutputTooLarge));
+}
+
+#[test]
+fn into_variants_append_and_reuse_buffers() {
+    let mut buf = b"prefix".to_vec();
+    let mut plain = Vec::new();
+    for seed in 0..4 {
+        let src: Vec<u8> = random_bytes(5_000 + seed as usize * 7_000, seed).iter().map(|b| b % 11).collect();
+        buf.truncate(6);
+        encode_into(&src, &mut buf);
+        assert_eq!(&buf[..6], b"prefix");
+        assert_eq!(&buf[6..], &encode(&src)[..]);
+
+        plain.clear();
+        decode_into(&buf[6..], &mut plain).unwrap();
+        assert_eq!(plain, src);
+    }
+
+    // A failed decode leaves the destination untouched.
+    let mut out = b"keep".to_vec();
+    assert_eq!(decode_into(&buf[6..buf.len() - 1], &mut out), Err(AnsxError::Truncated));
+    assert_eq!(out, b"keep");
+}
+
+#[test]
+fn max_encoded_len_bounds_every_mode() {
+    let inputs = [Vec::new(), vec![1], random_bytes(16_384, 14), random_bytes(100_000, 15)];
+    let options = [
+        EncodeOptions::new(),
+        EncodeOptions::new().interleave(Interleave::Eight),
+        EncodeOptions::new().model(Model::Adaptive),
+        EncodeOptions::new().checksum(false),
+    ];
+    for src in &inputs {
+        for opts in &options {
+            assert!(encode_with(src, opts).len() <= max_encoded_len(src.len()), "{opts:?}, {} bytes", src.len());
+        }
     }
-} 
\ No newline at end of file
+}
@@ -0,0 +1,110 @@
+use ansx::budget::{BudgetError, delta_ticks, verify};
+use ansx::rans::FreqTable;
+use ansx::*;
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+/// Skewed, zero, repeated and random blocks.
+fn mixed() -> Vec<u8> {
+    let skewed: Vec<u8> = random_bytes(16_384, 1).iter().map(|b| b.leading_zeros() as u8).collect();
+    let mut data = skewed.clone();
+    data.extend([0; 16_384]);
+    data.extend(&skewed);
+    data.extend(&skewed);
+    data.extend(random_bytes(5_000, 2));
+    data
+}
+
+#[test]
+fn reports_budget_per_block() {
+    let data = mixed();
+    let (encoded, report) = encode_verified(&data, &EncodeOptions::new(), &BudgetOptions::new()).unwrap();
+    assert_eq!(decode(&encoded).unwrap(), data);
+    println!("{:>7} {:>6} {:>8} {:>10} {:>8} {:>9}", "offset", "len", "budget", "entropy", "actual", "overhead");
+    for b in &report.blocks {
+        println!(
+            "{:>7} {:>6} {:>8} {:>10.1} {:>8} {:>9}",
+            b.offset, b.len, b.budget_bits, b.entropy_bits, b.actual_bits, b.overhead_bits
+        );
+    }
+    println!("{report}");
+
+    let offsets: Vec<usize> = report.blocks.iter().map(|b| b.offset).collect();
+    assert_eq!(offsets, [0, 16_384, 32_768, 49_152, 65_536]);
+    let skipped: Vec<bool> = report.blocks.iter().map(|b| b.skipped).collect();
+    assert_eq!(skipped, [false, true, false, true, false]);
+    for b in &report.blocks {
+        assert!(b.entropy_bits <= b.budget_bits as f64);
+        if b.skipped {
+            assert_eq!((b.budget_bits, b.actual_bits), (0, 0));
+            assert!(b.is_within(0));
+        } else {
+            // rANS lands near the entropy, below the rounded-up budget.
+            assert!((b.actual_bits as f64) < b.entropy_bits * 1.01 + 64.0, "{b:?}");
+        }
+    }
+    let total: u64 = report.blocks.iter().map(|b| (b.actual_bits + b.overhead_bits) / 8).sum();
+    assert_eq!(total as usize, encoded.len());
+    assert!(!report.is_within(), "the spec's ±1 bit is not met by order-0 rANS");
+}
+
+#[test]
+fn policy_selects_warning_or_error() {
+    let data = mixed();
+    let encoded = encode(&data);
+    let warn = verify(&data, &encoded, &BudgetOptions::new()).unwrap();
+    assert!(warn.violations().count() > 0);
+
+    let strict = BudgetOptions::new().policy(BudgetPolicy::Error);
+    match verify(&data, &encoded, &strict) {
+        Err(BudgetError::OverBudget(report)) => assert_eq!(report, warn),
+        other => panic!("expected OverBudget, got {other:?}"),
+    }
+
+    let loose = strict.tolerance_bits(u64::MAX);
+    assert!(verify(&data, &encoded, &loose).unwrap().is_within());
+    // All-zero input is all skips and meets any tolerance.
+    let zeros = vec![0; 40_000];
+    assert!(verify(&zeros, &encode(&zeros), &BudgetOptions::new().policy(BudgetPolicy::Error)).is_ok());
+}
+
+#[test]
+fn backends_and_models_are_measured() {
+    let data: Vec<u8> = random_bytes(100_000, 3).iter().map(|b| b.leading_zeros() as u8).collect();
+    for options in [
+        EncodeOptions::new().interleave(Interleave::Eight),
+        EncodeOptions::new().backend(Backend::Tans),
+        EncodeOptions::new().model(Model::Adaptive).checksum(false),
+    ] {
+        let (encoded, report) = encode_verified(&data, &options, &BudgetOptions::new()).unwrap();
+        let total = report.actual_bits() + report.overhead_bits();
+        assert_eq!(total as usize, encoded.len() * 8, "{options:?}");
+        assert!(report.actual_bits() < report.budget_bits() + 1024, "{options:?}: {report}");
+    }
+}
+
+#[test]
+fn rejects_mismatched_input() {
+    let data = mixed();
+    let encoded = encode(&data);
+    let options = BudgetOptions::new();
+    assert_eq!(verify(&data[1..], &encoded, &options), Err(BudgetError::Stream(AnsxError::Corrupt)));
+    let longer = [&data[..], &[1]].concat();
+    assert_eq!(verify(&longer, &encoded, &options), Err(BudgetError::Stream(AnsxError::Corrupt)));
+    assert_eq!(verify(&data, &encoded[..10], &options), Err(BudgetError::Stream(AnsxError::Truncated)));
+}
+
+#[test]
+fn delta_ticks_follow_the_table() {
+    let table = FreqTable::from_counts(&[1, 1, 2]).unwrap();
+    assert_eq!(delta_ticks(&table, &[0, 1, 2]), [4, 4, 2]);
+}
@@ -0,0 +1,116 @@
+use ansx::chunk::{
+    self, BACKEND_TANS, CHUNK_ID, Chunk, ChunkError, FLAG_ADAPTIVE, FLAG_PARITY, FLAG_REPEAT, HEADER_LEN, MAX_BLOCK_SIZE,
+};
+
+fn sample() -> Vec<u8> {
+    let mut out = Vec::new();
+    Chunk::new(16384, 0, b"payload").unwrap().write(&mut out);
+    out
+}
+
+#[test]
+fn write_then_parse() {
+    let bytes = sample();
+    assert_eq!(bytes.len(), HEADER_LEN + 7);
+    assert_eq!(&bytes[..4], &CHUNK_ID);
+    let (chunk, rest) = Chunk::parse(&bytes).unwrap();
+    assert!(rest.is_empty());
+    assert_eq!(chunk.header.block_size, 16384);
+    assert_eq!(chunk.header.cmpr_len, 7);
+    assert_eq!(chunk.header.parity_flag, 0);
+    assert_eq!(chunk.data, b"payload");
+}
+
+#[test]
+fn iterates_back_to_back_chunks() {
+    let mut bytes = sample();
+    Chunk::new(5, FLAG_PARITY, &[]).unwrap().write(&mut bytes);
+    let parsed: Vec<_> = chunk::chunks(&bytes).collect::<Result<_, _>>().unwrap();
+    assert_eq!(parsed.len(), 2);
+    assert_eq!(parsed[1].header.block_size, 5);
+    assert!(parsed[1].data.is_empty());
+}
+
+#[test]
+fn rejects_truncation() {
+    let bytes = sample();
+    assert_eq!(Chunk::parse(&bytes[..HEADER_LEN - 1]), Err(ChunkError::Truncated));
+    assert_eq!(Chunk::parse(&bytes[..bytes.len() - 1]), Err(ChunkError::Truncated));
+
+    // The largest valid cmpr_len: the payload end must not wrap on 32-bit.
+    let mut huge = bytes.clone();
+    huge[4..8].copy_from_slice(&u32::MAX.to_le_bytes());
+    huge[10..14].copy_from_slice(&(u32::MAX - 10).to_le_bytes());
+    assert_eq!(Chunk::parse(&huge), Err(ChunkError::Truncated));
+}
+
+#[test]
+fn rejects_bad_id() {
+    let mut bytes = sample();
+    bytes[3] = b'2';
+    assert_eq!(Chunk::parse(&bytes), Err(ChunkError::BadId));
+}
+
+#[test]
+fn rejects_length_mismatch() {
+    let mut bytes = sample();
+    bytes[4] += 1;
+    assert_eq!(Chunk::parse(&bytes), Err(ChunkError::LengthMismatch));
+}
+
+#[test]
+fn rejects_reserved_and_flags() {
+    let mut bytes = sample();
+    bytes[17] = 1;
+    assert_eq!(Chunk::parse(&bytes), Err(ChunkError::ReservedNonZero));
+    let mut bytes = sample();
+    bytes[16] = 2;
+    assert_eq!(Chunk::parse(&bytes), Err(ChunkError::UnknownBackend(2)));
+    let mut bytes = sample();
+    bytes[14] = 0x80;
+    assert_eq!(Chunk::parse(&bytes), Err(ChunkError::UnknownFlags(0x80)));
+}
+
+#[test]
+fn rejects_block_size_out_of_range() {
+    assert_eq!(Chunk::new(0, 0, &[]), Err(ChunkError::BlockSize(0)));
+    assert_eq!(Chunk::new(MAX_BLOCK_SIZE + 1, 0, &[]), Err(ChunkError::BlockSize(MAX_BLOCK_SIZE + 1)));
+    let mut bytes = sample();
+    bytes[8..10].copy_from_slice(&0u16.to_le_bytes());
+    assert_eq!(Chunk::parse(&bytes), Err(ChunkError::BlockSize(0)));
+}
+
+#[test]
+fn rejects_inconsistent_skip_flags() {
+    assert_eq!(Chunk::new(16, FLAG_PARITY, b"x"), Err(ChunkError::InvalidFlags(FLAG_PARITY)));
+    assert_eq!(Chunk::new(16, FLAG_REPEAT, &[]), Err(ChunkError::InvalidFlags(FLAG_REPEAT)));
+    assert!(Chunk::new(16, FLAG_PARITY | FLAG_REPEAT, &[]).is_ok());
+    assert_eq!(Chunk::new(16, FLAG_PARITY | FLAG_ADAPTIVE, &[]), Err(ChunkError::InvalidFlags(FLAG_PARITY | FLAG_ADAPTIVE)));
+    assert!(Chunk::new(16, FLAG_ADAPTIVE, b"x").is_ok());
+}
+
+#[test]
+fn lanes_roundtrip_and_validate() {
+    let mut bytes = Vec::new();
+    Chunk::new(64, 0, b"x").unwrap().with_lanes(8).unwrap().write(&mut bytes);
+    assert_eq!(Chunk::parse(&bytes).unwrap().0.header.lanes, 8);
+    bytes[15] = 3;
+    assert_eq!(Chunk::parse(&bytes), Err(ChunkError::UnknownLanes(3)));
+    assert_eq!(Chunk::new(64, 0, b"x").unwrap().with_lanes(2), Err(ChunkError::UnknownLanes(2)));
+}
+
+#[test]
+fn backend_byte() {
+    let mut bytes = sample();
+    bytes[16] = BACKEND_TANS;
+    assert_eq!(Chunk::parse(&bytes).unwrap().0.header.backend, BACKEND_TANS);
+    let tans = Chunk::new(16, 0, b"x").unwrap().with_backend(BACKEND_TANS).unwrap();
+    let mut out = Vec::new();
+    tans.write(&mut out);
+    assert_eq!(out[16], BACKEND_TANS);
+
+    let invalid = Err(ChunkError::InvalidBackend(BACKEND_TANS));
+    assert_eq!(Chunk::new(16, FLAG_PARITY, &[]).unwrap().with_backend(BACKEND_TANS), invalid);
+    assert_eq!(Chunk::new(16, FLAG_ADAPTIVE, b"x").unwrap().with_backend(BACKEND_TANS), invalid);
+    assert_eq!(tans.with_lanes(4), invalid);
+}
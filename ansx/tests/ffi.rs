@@ -0,0 +1,348 @@
+use ansx::chunk::{Chunk, FLAG_PARITY, MAX_BLOCK_SIZE};
+use ansx::*;
+use std::ptr;
+
+/// Encodes then decodes `src` through the C ABI, returning the decoded bytes.
+fn roundtrip(src: &[u8]) -> Vec<u8> {
+    let mut enc_len: u32 = 0;
+    let mut dec_len: u32 = 0;
+    unsafe {
+        let enc = ansx_encode(src.as_ptr(), src.len() as _, &mut enc_len as *mut _);
+        assert!(!enc.is_null());
+        let dec = ansx_decode(enc, enc_len, &mut dec_len as *mut _);
+        assert!(!dec.is_null());
+        let decoded = std::slice::from_raw_parts(dec, dec_len as usize).to_vec();
+        ansx_free(enc.cast(), enc_len);
+        ansx_free(dec.cast(), dec_len);
+        decoded
+    }
+}
+
+#[test]
+fn roundtrip_c_abi() {
+    let src = b"hello world";
+    assert_eq!(&roundtrip(src)[..], src);
+    assert!(roundtrip(&[]).is_empty());
+}
+
+#[test]
+fn c_abi_matches_rust_api() {
+    let src = b"abracadabra abracadabra abracadabra";
+    let mut enc_len: u32 = 0;
+    unsafe {
+        let enc = ansx_encode(src.as_ptr(), src.len() as _, &mut enc_len as *mut _);
+        let bytes = std::slice::from_raw_parts(enc, enc_len as usize).to_vec();
+        ansx_free(enc.cast(), enc_len);
+        assert_eq!(bytes, encode(src));
+    }
+}
+
+#[test]
+fn decode_rejects_corrupt_stream() {
+    let mut bytes = encode(b"abracadabra abracadabra abracadabra");
+    bytes.truncate(bytes.len() - 1);
+    let mut dec_len: u32 = 1;
+    unsafe {
+        let dec = ansx_decode(bytes.as_ptr(), bytes.len() as _, &mut dec_len as *mut _);
+        assert!(dec.is_null());
+        assert_eq!(dec_len, 0);
+    }
+}
+
+/// Runs `ansx_decode2` on `input`, returning its status and decoded bytes.
+fn decode2(input: &[u8]) -> (i32, Vec<u8>) {
+    let mut out = ptr::null_mut();
+    let mut out_len: u32 = 7;
+    unsafe {
+        let status = ansx_decode2(input.as_ptr(), input.len() as _, &mut out, &mut out_len);
+        if status != ANSX_OK {
+            assert!(out.is_null());
+            assert_eq!(out_len, 0);
+            return (status, Vec::new());
+        }
+        let decoded = std::slice::from_raw_parts(out, out_len as usize).to_vec();
+        ansx_free(out.cast(), out_len);
+        (status, decoded)
+    }
+}
+
+const SAMPLE: &[u8] = b"abracadabra abracadabra abracadabra";
+
+#[test]
+fn decode2_ok() {
+    assert_eq!(decode2(&encode(SAMPLE)), (ANSX_OK, SAMPLE.to_vec()));
+    assert_eq!(decode2(&[]), (ANSX_OK, Vec::new()));
+}
+
+#[test]
+fn decode2_invalid_argument() {
+    let mut out = ptr::null_mut();
+    let mut out_len: u32 = 0;
+    unsafe {
+        assert_eq!(ansx_decode2(ptr::null(), 0, &mut out, &mut out_len), ANSX_ERR_INVALID_ARGUMENT);
+        assert_eq!(ansx_decode2(SAMPLE.as_ptr(), 1, ptr::null_mut(), &mut out_len), ANSX_ERR_INVALID_ARGUMENT);
+        assert_eq!(ansx_decode2(SAMPLE.as_ptr(), 1, &mut out, ptr::null_mut()), ANSX_ERR_INVALID_ARGUMENT);
+    }
+}
+
+#[test]
+fn decode2_truncated() {
+    let encoded = encode(SAMPLE);
+    assert_eq!(decode2(&encoded[..encoded.len() - 1]).0, ANSX_ERR_TRUNCATED);
+}
+
+#[test]
+fn decode2_bad_header() {
+    let mut encoded = encode(SAMPLE);
+    encoded[0] = b'X';
+    assert_eq!(decode2(&encoded).0, ANSX_ERR_BAD_HEADER);
+}
+
+#[test]
+fn decode2_corrupt() {
+    let encoded = encode_with(SAMPLE, &EncodeOptions::new().checksum(false));
+    let (chunk, _) = Chunk::parse(&encoded).unwrap();
+    let payload = [chunk.data, &[0]].concat();
+    let mut tampered = Vec::new();
+    Chunk::new(chunk.header.block_size, 0, &payload).unwrap().write(&mut tampered);
+    assert_eq!(decode2(&tampered).0, ANSX_ERR_CORRUPT);
+}
+
+#[test]
+fn decode2_checksum() {
+    let mut encoded = encode(SAMPLE);
+    *encoded.last_mut().unwrap() ^= 1;
+    assert_eq!(decode2(&encoded).0, ANSX_ERR_CHECKSUM);
+}
+
+#[test]
+fn decode2_output_too_large() {
+    // 4 GiB + 32 KiB of zero blocks in about 2.4 MB of headers.
+    let mut zeros = Vec::new();
+    for _ in 0..=(u32::MAX as usize / MAX_BLOCK_SIZE as usize) + 1 {
+        Chunk::new(MAX_BLOCK_SIZE, FLAG_PARITY, &[]).unwrap().write(&mut zeros);
+    }
+    assert_eq!(decode2(&zeros).0, ANSX_ERR_OUTPUT_TOO_LARGE);
+    let mut len: u32 = 1;
+    unsafe {
+        assert!(ansx_decode(zeros.as_ptr(), zeros.len() as _, &mut len).is_null());
+    }
+    assert_eq!(len, 0);
+}
+
+#[test]
+fn encode_into_fills_caller_buffer() {
+    let src: Vec<u8> = (0..50_000u32).map(|i| (i * i % 7) as u8).collect();
+    let cap = ansx_max_encoded_len(src.len() as u32);
+    let mut buf = vec![0u8; cap as usize];
+    let mut enc_len: u32 = 0;
+    let status = unsafe { ansx_encode_into(src.as_ptr(), src.len() as _, buf.as_mut_ptr(), cap, &mut enc_len) };
+    assert_eq!(status, ANSX_OK);
+    assert_eq!(&buf[..enc_len as usize], &encode(&src)[..]);
+
+    // The exact size suffices; one byte less reports the same size.
+    let mut exact = vec![0u8; enc_len as usize];
+    let mut len: u32 = 0;
+    let status = unsafe { ansx_encode_into(src.as_ptr(), src.len() as _, exact.as_mut_ptr(), enc_len, &mut len) };
+    assert_eq!((status, len), (ANSX_OK, enc_len));
+    assert_eq!(exact, &buf[..enc_len as usize]);
+    let status = unsafe { ansx_encode_into(src.as_ptr(), src.len() as _, exact.as_mut_ptr(), enc_len - 1, &mut len) };
+    assert_eq!((status, len), (ANSX_ERR_BUFFER_TOO_SMALL, enc_len));
+
+    let mut out = vec![0u8; src.len()];
+    let mut dec_len: u32 = 0;
+    let status = unsafe { ansx_decode_into(buf.as_ptr(), enc_len, out.as_mut_ptr(), out.len() as _, &mut dec_len) };
+    assert_eq!(status, ANSX_OK);
+    assert_eq!(dec_len as usize, src.len());
+    assert_eq!(out, src);
+}
+
+#[test]
+fn into_reports_required_size() {
+    let encoded = encode(SAMPLE);
+    let mut needed: u32 = 0;
+    unsafe {
+        let status = ansx_encode_into(SAMPLE.as_ptr(), SAMPLE.len() as _, ptr::null_mut(), 0, &mut needed);
+        assert_eq!(status, ANSX_ERR_BUFFER_TOO_SMALL);
+        assert_eq!(needed as usize, encoded.len());
+
+        let mut small = [0u8; 8];
+        let status = ansx_decode_into(encoded.as_ptr(), encoded.len() as _, small.as_mut_ptr(), 8, &mut needed);
+        assert_eq!(status, ANSX_ERR_BUFFER_TOO_SMALL);
+        assert_eq!(needed as usize, SAMPLE.len());
+        assert_eq!(small, [0; 8], "nothing is written when the buffer is too small");
+    }
+}
+
+#[test]
+fn into_reports_decode_errors() {
+    let mut encoded = encode(SAMPLE);
+    *encoded.last_mut().unwrap() ^= 1;
+    let mut out = [0u8; 64];
+    let mut out_len: u32 = 9;
+    unsafe {
+        let status = ansx_decode_into(encoded.as_ptr(), encoded.len() as _, out.as_mut_ptr(), 64, &mut out_len);
+        assert_eq!(status, ANSX_ERR_CHECKSUM);
+        assert_eq!(out_len, 0);
+        let status = ansx_decode_into(encoded.as_ptr(), 3, out.as_mut_ptr(), 64, &mut out_len);
+        assert_eq!(status, ANSX_ERR_TRUNCATED);
+        let status = ansx_encode_into(SAMPLE.as_ptr(), 1, ptr::null_mut(), 64, &mut out_len);
+        assert_eq!(status, ANSX_ERR_INVALID_ARGUMENT);
+    }
+}
+
+#[test]
+fn max_encoded_len_saturates() {
+    assert_eq!(ansx_max_encoded_len(0), 0);
+    assert_eq!(ansx_max_encoded_len(u32::MAX), 0);
+}
+
+#[test]
+fn size_t_entry_points_match_32_bit() {
+    let encoded = encode(SAMPLE);
+    let mut enc_len: usize = 0;
+    let mut dec_len: usize = 0;
+    unsafe {
+        let enc = ansx_encode64(SAMPLE.as_ptr(), SAMPLE.len(), &mut enc_len);
+        assert_eq!(std::slice::from_raw_parts(enc, enc_len), &encoded[..]);
+        let dec = ansx_decode64(enc, enc_len, &mut dec_len);
+        assert_eq!(std::slice::from_raw_parts(dec, dec_len), SAMPLE);
+        ansx_free64(enc.cast(), enc_len);
+        ansx_free64(dec.cast(), dec_len);
+
+        let mut out = ptr::null_mut();
+        assert_eq!(ansx_decode2_64(encoded.as_ptr(), encoded.len(), &mut out, &mut dec_len), ANSX_OK);
+        assert_eq!(std::slice::from_raw_parts(out, dec_len), SAMPLE);
+        ansx_free64(out.cast(), dec_len);
+
+        let cap = ansx_max_encoded_len64(SAMPLE.len());
+        assert_eq!(cap, ansx_max_encoded_len(SAMPLE.len() as u32) as usize);
+        let mut buf = vec![0u8; cap];
+        let status = ansx_encode_into64(SAMPLE.as_ptr(), SAMPLE.len(), buf.as_mut_ptr(), cap, &mut enc_len);
+        assert_eq!(status, ANSX_OK);
+        assert_eq!(&buf[..enc_len], &encoded[..]);
+        let mut out = vec![0u8; SAMPLE.len()];
+        let status = ansx_decode_into64(buf.as_ptr(), enc_len, out.as_mut_ptr(), out.len(), &mut dec_len);
+        assert_eq!(status, ANSX_OK);
+        assert_eq!(&out[..dec_len], SAMPLE);
+    }
+}
+
+#[test]
+fn encode_rejects_output_past_u32() {
+    // Zeroed allocations are lazily mapped; the input is rejected before it is read.
+    let src = vec![0u8; u32::MAX as usize];
+    let mut out_len: u32 = 1;
+    unsafe {
+        assert!(ansx_encode(src.as_ptr(), u32::MAX, &mut out_len).is_null());
+        assert_eq!(out_len, 0);
+        out_len = 1;
+        let status = ansx_encode_into(src.as_ptr(), u32::MAX, ptr::null_mut(), 0, &mut out_len);
+        assert_eq!(status, ANSX_ERR_OUTPUT_TOO_LARGE);
+        assert_eq!(out_len, 0);
+    }
+}
+
+#[test]
+fn size_t_decode_describes_output_past_u32() {
+    let mut zeros = Vec::new();
+    let blocks = (u32::MAX as usize / MAX_BLOCK_SIZE as usize) + 2;
+    for _ in 0..blocks {
+        Chunk::new(MAX_BLOCK_SIZE, FLAG_PARITY, &[]).unwrap().write(&mut zeros);
+    }
+    let mut needed: usize = 0;
+    let status = unsafe { ansx_decode_into64(zeros.as_ptr(), zeros.len(), ptr::null_mut(), 0, &mut needed) };
+    assert_eq!(status, ANSX_ERR_BUFFER_TOO_SMALL);
+    assert_eq!(needed, blocks * MAX_BLOCK_SIZE as usize);
+}
+
+#[test]
+fn max_encoded_len64_covers_large_inputs() {
+    let bound = ansx_max_encoded_len64(u32::MAX as usize);
+    assert!(bound > u32::MAX as usize);
+    assert_eq!(bound, max_encoded_len(u32::MAX as usize));
+    assert_eq!(ansx_max_encoded_len64(usize::MAX), 0);
+}
+
+/// Encodes `src` through the streaming C handle, feeding and draining in pieces.
+fn c_stream_encode(src: &[u8], piece: usize) -> Vec<u8> {
+    let mut out = Vec::new();
+    let mut buf = vec![0u8; 3000];
+    unsafe {
+        let enc = ansx_encoder_new();
+        let drain = |out: &mut Vec<u8>, buf: &mut [u8]| loop {
+            let mut n = 0usize;
+            assert_eq!(ansx_encoder_read(enc, buf.as_mut_ptr(), buf.len(), &mut n), ANSX_OK);
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        };
+        for part in src.chunks(piece) {
+            assert_eq!(ansx_encoder_write(enc, part.as_ptr(), part.len()), ANSX_OK);
+            drain(&mut out, &mut buf);
+        }
+        assert_eq!(ansx_encoder_finish(enc), ANSX_OK);
+        assert_eq!(ansx_encoder_write(enc, src.as_ptr(), 0), ANSX_ERR_INVALID_ARGUMENT);
+        drain(&mut out, &mut buf);
+        ansx_encoder_free(enc);
+    }
+    out
+}
+
+/// Decodes `input` through the streaming C handle, returning the output and
+/// the status of `ansx_decoder_finish`.
+fn c_stream_decode(input: &[u8], piece: usize) -> (Vec<u8>, i32) {
+    let mut out = Vec::new();
+    let mut buf = vec![0u8; 5000];
+    unsafe {
+        let dec = ansx_decoder_new();
+        for part in input.chunks(piece) {
+            assert_eq!(ansx_decoder_write(dec, part.as_ptr(), part.len()), ANSX_OK);
+            loop {
+                let mut n = 0usize;
+                let status = ansx_decoder_read(dec, buf.as_mut_ptr(), buf.len(), &mut n);
+                if status != ANSX_OK {
+                    ansx_decoder_free(dec);
+                    return (out, status);
+                }
+                out.extend_from_slice(&buf[..n]);
+                if n < buf.len() {
+                    break;
+                }
+            }
+        }
+        let status = ansx_decoder_finish(dec);
+        ansx_decoder_free(dec);
+        (out, status)
+    }
+}
+
+#[test]
+fn streaming_handles_roundtrip() {
+    let src: Vec<u8> = (0..70_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 29) as u8).collect();
+    let expected = encode(&src);
+    for piece in [1, 999, 16_384, 100_000] {
+        let encoded = c_stream_encode(&src, piece);
+        assert_eq!(encoded, expected, "{piece}-byte writes");
+        assert_eq!(c_stream_decode(&encoded, piece), (src.clone(), ANSX_OK), "{piece}-byte writes");
+    }
+}
+
+#[test]
+fn streaming_decoder_reports_errors() {
+    let encoded = encode(&[3u8; 40_000]);
+    let (out, status) = c_stream_decode(&encoded[..encoded.len() - 2], 100);
+    assert_eq!(status, ANSX_ERR_TRUNCATED);
+    assert_eq!(out.len(), 32_768, "complete chunks are still decoded");
+
+    let mut bad = encoded.clone();
+    bad[1] = 0;
+    assert_eq!(c_stream_decode(&bad, 100).1, ANSX_ERR_BAD_HEADER);
+
+    unsafe {
+        assert_eq!(ansx_decoder_write(ptr::null_mut(), bad.as_ptr(), 1), ANSX_ERR_INVALID_ARGUMENT);
+        ansx_decoder_free(ptr::null_mut());
+        ansx_encoder_free(ptr::null_mut());
+    }
+}
@@ -0,0 +1,101 @@
+use ansx::gaussian::BLOCK_SYMBOLS;
+use ansx::rans::PROB_SCALE;
+use ansx::{AnsxError, BlockParams, GaussianTableBuilder, decode_gaussian, encode_gaussian};
+
+/// Approximately normal samples (Irwin–Hall sum of 12 uniforms) around `mu`.
+fn gaussian_symbols(len: usize, mu: f64, sigma: f64, mut seed: u64) -> Vec<u16> {
+    let mut uniform = move || {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        (seed >> 11) as f64 / (1u64 << 53) as f64
+    };
+    (0..len)
+        .map(|_| {
+            let z: f64 = (0..12).map(|_| uniform()).sum::<f64>() - 6.0;
+            (mu + z * sigma).round().clamp(0.0, 65535.0) as u16
+        })
+        .collect()
+}
+
+#[test]
+fn table_normalizes_with_nonzero_freqs() {
+    for (mu, sigma) in [(0.0, 1.0), (32768.0, 12.5), (65535.0, 300.0), (100.0, 1e6), (5.0, 0.01)] {
+        let table = GaussianTableBuilder::new().build(BlockParams { mu, sigma }).unwrap();
+        let freqs = table.freq_table().freqs();
+        assert_eq!(freqs.iter().sum::<u32>(), PROB_SCALE);
+        assert!(freqs.iter().all(|&f| f >= 1), "zero frequency for mu={mu} sigma={sigma}");
+    }
+}
+
+#[test]
+fn wide_sigma_fills_the_largest_window() {
+    // σ far beyond the window: every entry is close to the floor of 1 and the
+    // leftover share must still land on the mode without overshooting.
+    for span in [1, 4096, PROB_SCALE / 2, PROB_SCALE - 1] {
+        let builder = GaussianTableBuilder::new().max_span(span);
+        for (mu, sigma) in [(0.0, 1e9), (32768.0, 1e6), (65535.0, 3e4), (32768.0, f32::MAX)] {
+            let table = builder.build(BlockParams { mu, sigma }).unwrap();
+            let freqs = table.freq_table().freqs();
+            assert!(table.window().len() as u32 <= span, "mu={mu} sigma={sigma}");
+            assert_eq!(freqs.iter().sum::<u32>(), PROB_SCALE);
+            assert!(freqs.iter().all(|&f| f >= 1), "zero frequency for span={span} mu={mu} sigma={sigma}");
+        }
+        let centred = builder.build(BlockParams { mu: 32768.0, sigma: 1e9 }).unwrap();
+        assert_eq!(centred.window().len() as u32, span);
+    }
+}
+
+#[test]
+fn table_is_bit_stable() {
+    // Pinned FNV-1a digest: any platform-dependent arithmetic would change it.
+    let table = GaussianTableBuilder::new().build(BlockParams { mu: 1234.5, sigma: 37.25 }).unwrap();
+    let digest = table.freq_table().freqs().iter().fold(0xcbf2_9ce4_8422_2325u64, |h, &f| {
+        (h ^ f as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    assert_eq!(digest, 0x9bd0fca078a5a457);
+}
+
+#[test]
+fn rejects_bad_params() {
+    let builder = GaussianTableBuilder::new();
+    assert_eq!(builder.build(BlockParams { mu: 0.0, sigma: 0.0 }), Err(AnsxError::InvalidModel));
+    assert_eq!(builder.build(BlockParams { mu: f32::NAN, sigma: 1.0 }), Err(AnsxError::InvalidModel));
+    let params = [BlockParams { mu: 0.0, sigma: 1.0 }];
+    assert_eq!(encode_gaussian(&vec![0u16; BLOCK_SYMBOLS + 1], &params), Err(AnsxError::InvalidModel));
+}
+
+#[test]
+fn roundtrip_matched_model_compresses() {
+    let mu = 20_000.0;
+    let sigma = 40.0;
+    let symbols = gaussian_symbols(3 * BLOCK_SYMBOLS + 17, mu, sigma, 42);
+    let params = vec![BlockParams { mu: mu as f32, sigma: sigma as f32 }; 4];
+    let encoded = encode_gaussian(&symbols, &params).unwrap();
+    assert_eq!(decode_gaussian(&encoded, &params).unwrap(), symbols);
+    // log2(σ·√(2πe)) ≈ 7.4 bits per symbol versus 16 raw.
+    let bits_per_symbol = encoded.len() as f64 * 8.0 / symbols.len() as f64;
+    assert!(bits_per_symbol < 7.6, "{bits_per_symbol} bits/symbol");
+}
+
+#[test]
+fn roundtrip_out_of_window_symbols() {
+    let symbols: Vec<u16> = (0..BLOCK_SYMBOLS as u32).map(|i| (i.wrapping_mul(40_503) % 65_536) as u16).collect();
+    let params = [BlockParams { mu: 500.0, sigma: 2.0 }];
+    let encoded = encode_gaussian(&symbols, &params).unwrap();
+    assert_eq!(decode_gaussian(&encoded, &params).unwrap(), symbols);
+}
+
+#[test]
+fn roundtrip_empty() {
+    let encoded = encode_gaussian(&[], &[]).unwrap();
+    assert!(decode_gaussian(&encoded, &[]).unwrap().is_empty());
+}
+
+#[test]
+fn decode_rejects_truncation() {
+    let symbols = gaussian_symbols(1000, 300.0, 10.0, 1);
+    let params = [BlockParams { mu: 300.0, sigma: 10.0 }];
+    let encoded = encode_gaussian(&symbols, &params).unwrap();
+    assert_eq!(decode_gaussian(&encoded[..encoded.len() - 1], &params), Err(AnsxError::Truncated));
+}
@@ -0,0 +1,95 @@
+use ansx::chunk::{self, Chunk};
+use ansx::rans::{FreqTable, InterleavedEncoder, RansEncoder};
+use ansx::{EncodeOptions, Interleave, decode, encode_with};
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+fn skewed(len: usize, seed: u64) -> Vec<u8> {
+    random_bytes(len, seed).iter().map(|b| b.leading_zeros() as u8).collect()
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |h, &b| (h ^ b as u64).wrapping_mul(0x0100_0000_01b3))
+}
+
+const MODES: [Interleave; 3] = [Interleave::Single, Interleave::Four, Interleave::Eight];
+
+#[test]
+fn roundtrip_all_lane_counts() {
+    let inputs = [
+        Vec::new(),
+        vec![42],
+        b"abcdefg".to_vec(),
+        skewed(9, 1),
+        skewed(100_003, 2),
+        random_bytes(40_000, 3),
+        vec![0x11; 20_000],
+    ];
+    for mode in MODES {
+        let options = EncodeOptions::new().interleave(mode);
+        for src in &inputs {
+            let encoded = encode_with(src, &options);
+            assert_eq!(&decode(&encoded).unwrap(), src, "{mode:?}, {} bytes", src.len());
+        }
+    }
+}
+
+#[test]
+fn lanes_are_signalled_in_header() {
+    let src = skewed(50_000, 4);
+    for mode in MODES {
+        let encoded = encode_with(&src, &EncodeOptions::new().interleave(mode));
+        for c in chunk::chunks(&encoded) {
+            assert_eq!(c.unwrap().header.lanes, mode.lanes());
+        }
+    }
+}
+
+#[test]
+fn interleaving_costs_only_extra_states() {
+    let src = skewed(16_384, 5);
+    let single = encode_with(&src, &EncodeOptions::new()).len();
+    let eight = encode_with(&src, &EncodeOptions::new().interleave(Interleave::Eight)).len();
+    assert!(eight <= single + 7 * 4 + 8, "single {single}, eight {eight}");
+}
+
+#[test]
+fn streams_are_bit_stable() {
+    // Pinned digests: the byte stream must not depend on the target architecture.
+    let src = skewed(30_000, 6);
+    let mut counts = [0u32; 256];
+    for &b in &src {
+        counts[b as usize] += 1;
+    }
+    let table = FreqTable::from_counts(&counts).unwrap();
+    let mut single = RansEncoder::new();
+    for &b in src.iter().rev() {
+        single.put_symbol(&table, b as usize);
+    }
+    assert_eq!(single.finish(), InterleavedEncoder::<1>::new().encode(&table, &src));
+    let digests = [
+        fnv1a(&InterleavedEncoder::<1>::new().encode(&table, &src)),
+        fnv1a(&InterleavedEncoder::<4>::new().encode(&table, &src)),
+        fnv1a(&InterleavedEncoder::<8>::new().encode(&table, &src)),
+    ];
+    assert_eq!(digests, [0x7ab1926aaa556a69, 0x1e648987e160ba6, 0x84b16594e658a308]);
+}
+
+#[test]
+fn wrong_lane_count_is_rejected() {
+    let src = skewed(1000, 7);
+    let encoded = encode_with(&src, &EncodeOptions::new().interleave(Interleave::Four));
+    let (c, _) = Chunk::parse(&encoded).unwrap();
+    let mut relabelled = Vec::new();
+    Chunk::new(c.header.block_size, 0, c.data).unwrap().with_lanes(8).unwrap().write(&mut relabelled);
+    assert!(decode(&relabelled).is_err());
+}
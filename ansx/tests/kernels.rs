@@ -0,0 +1,68 @@
+//! Scalar and AVX2 kernels must produce identical bytes. On CPUs without AVX2
+//! both options run the scalar path and the comparisons hold trivially.
+
+use ansx::gaussian::{BLOCK_SYMBOLS, encode_gaussian_with};
+use ansx::{
+    BlockParams, DecodeOptions, EncodeOptions, GaussianTableBuilder, Interleave, Kernel, decode_with, encode_with,
+};
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+const PARAMS: [(f32, f32); 7] = [
+    (0.0, 1.0),
+    (1234.5, 37.25),
+    (32768.0, 0.3),
+    (40000.25, 900.0),
+    (65535.0, 12.0),
+    (-50.0, 20.0),
+    (300.0, 1e5),
+];
+
+#[test]
+fn gaussian_tables_match_across_kernels() {
+    let scalar = GaussianTableBuilder::new().kernel(Kernel::Scalar);
+    let avx2 = GaussianTableBuilder::new().kernel(Kernel::Avx2);
+    for (mu, sigma) in PARAMS {
+        let p = BlockParams { mu, sigma };
+        assert_eq!(scalar.build(p).unwrap(), avx2.build(p).unwrap(), "mu={mu} sigma={sigma}");
+    }
+}
+
+#[test]
+fn gaussian_encode_matches_across_kernels() {
+    let symbols: Vec<u16> = random_bytes(4 * BLOCK_SYMBOLS, 11)
+        .chunks(2)
+        .map(|b| 1000 + (b[0] as u16 % 64) + (b[1] as u16 % 64))
+        .collect();
+    let params = [BlockParams { mu: 1063.0, sigma: 26.0 }; 2];
+    let scalar = encode_gaussian_with(&GaussianTableBuilder::new().kernel(Kernel::Scalar), &symbols, &params);
+    let avx2 = encode_gaussian_with(&GaussianTableBuilder::new().kernel(Kernel::Avx2), &symbols, &params);
+    assert_eq!(scalar.unwrap(), avx2.unwrap());
+}
+
+#[test]
+fn interleaved_decode_matches_across_kernels() {
+    let inputs = [
+        random_bytes(100_001, 1),
+        random_bytes(70_000, 2).iter().map(|b| b.trailing_zeros() as u8).collect(),
+        b"eight lanes, then a tail".to_vec(),
+    ];
+    let scalar = DecodeOptions::new().kernel(Kernel::Scalar);
+    let avx2 = DecodeOptions::new().kernel(Kernel::Avx2);
+    for src in &inputs {
+        let encoded = encode_with(src, &EncodeOptions::new().interleave(Interleave::Eight));
+        assert_eq!(&decode_with(&encoded, &scalar).unwrap(), src);
+        assert_eq!(&decode_with(&encoded, &avx2).unwrap(), src);
+        let cut = &encoded[..encoded.len() - 1];
+        assert_eq!(decode_with(cut, &scalar), decode_with(cut, &avx2));
+    }
+}
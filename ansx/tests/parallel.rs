@@ -0,0 +1,78 @@
+#![cfg(feature = "parallel")]
+
+use ansx::chunk::{Chunk, FLAG_PARITY, FLAG_REPEAT};
+use ansx::*;
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+/// Skewed bytes with runs of zero blocks and repeated blocks mixed in.
+fn mixed(len: usize) -> Vec<u8> {
+    let mut data: Vec<u8> = random_bytes(len, 7).iter().map(|b| b.leading_zeros() as u8).collect();
+    data[40_000..80_000].fill(0);
+    let block = data[100_000..116_384].to_vec();
+    for k in 1..4 {
+        data[100_000 + k * 16_384..][..16_384].copy_from_slice(&block);
+    }
+    data
+}
+
+fn pool(threads: usize) -> rayon::ThreadPool {
+    rayon::ThreadPoolBuilder::new().num_threads(threads).build().unwrap()
+}
+
+#[test]
+fn output_matches_serial_for_any_thread_count() {
+    let data = mixed(400_003);
+    for options in [
+        EncodeOptions::new(),
+        EncodeOptions::new().interleave(Interleave::Eight),
+        EncodeOptions::new().model(Model::Adaptive).checksum(false),
+    ] {
+        let serial = encode_with(&data, &options);
+        for threads in [1, 2, 3, 8] {
+            let (encoded, decoded) =
+                pool(threads).install(|| (encode_parallel_with(&data, &options), decode_parallel(&serial)));
+            assert_eq!(encoded, serial, "{threads} threads");
+            assert_eq!(decoded.unwrap(), data, "{threads} threads");
+        }
+    }
+    assert!(encode_parallel(&[]).is_empty());
+    assert_eq!(decode_parallel(&[]).unwrap(), b"");
+}
+
+#[test]
+fn reports_first_error_like_serial() {
+    let data = mixed(200_000);
+    let mut encoded = encode(&data);
+    // Corrupt the last byte of the first and last chunks.
+    let first = encoded.len() - Chunk::parse(&encoded).unwrap().1.len();
+    encoded[first - 1] ^= 1;
+    *encoded.last_mut().unwrap() ^= 1;
+    assert_eq!(decode_parallel(&encoded).unwrap_err(), decode(&encoded).unwrap_err());
+    assert_eq!(decode_parallel(&encoded[..encoded.len() - 1]), Err(AnsxError::Truncated));
+
+    // A repeat with no previous block.
+    let mut bad = Vec::new();
+    Chunk::new(16, FLAG_PARITY | FLAG_REPEAT, &[]).unwrap().write(&mut bad);
+    assert_eq!(decode_parallel(&bad), Err(AnsxError::Corrupt));
+}
+
+#[test]
+fn output_limit() {
+    let mut zeros = Vec::new();
+    for _ in 0..4 {
+        Chunk::new(1024, FLAG_PARITY, &[]).unwrap().write(&mut zeros);
+    }
+    let limited = DecodeOptions::new().max_output(4095);
+    assert_eq!(decode_parallel_with(&zeros, &limited), Err(AnsxError::OutputTooLarge));
+    assert_eq!(decode_parallel_with(&zeros, &DecodeOptions::new().max_output(4096)).unwrap(), vec![0; 4096]);
+}
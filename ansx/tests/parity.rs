@@ -0,0 +1,92 @@
+//! Parity skipping must never lose data: blocks whose parity is even under the
+//! design-doc rule but whose content is not reconstructible must be coded.
+
+use ansx::chunk::{self, DEFAULT_BLOCK_SIZE, FLAG_PARITY, FLAG_REPEAT};
+use ansx::{decode, encode};
+
+const BLOCK: usize = DEFAULT_BLOCK_SIZE as usize;
+
+/// Curvature parity from docs/rbt_entropy_map.md, applied to byte symbols.
+fn parity_even(block: &[u8]) -> bool {
+    let sum: u64 = block
+        .iter()
+        .map(|&s| {
+            let x = s as f64 / 255.0;
+            (x * (1.0 - x) * 65535.0) as u64
+        })
+        .sum();
+    sum.is_multiple_of(2)
+}
+
+/// Flips low bits of the last byte until the block has even parity.
+fn force_even(mut block: Vec<u8>) -> Vec<u8> {
+    let last = block.len() - 1;
+    while !parity_even(&block) {
+        block[last] = block[last].wrapping_add(1);
+    }
+    block
+}
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+/// Skip bits of each chunk's `parity_flag`.
+fn flags(encoded: &[u8]) -> Vec<u8> {
+    chunk::chunks(encoded).map(|c| c.unwrap().header.parity_flag & (FLAG_PARITY | FLAG_REPEAT)).collect()
+}
+
+#[test]
+fn even_parity_nonzero_blocks_roundtrip() {
+    let mut lone = vec![0u8; BLOCK];
+    lone[BLOCK / 2] = 0x80;
+    let adversarial = [
+        force_even(random_bytes(BLOCK, 3)),
+        force_even(vec![0x55; BLOCK]),
+        force_even(lone),
+        force_even((0..BLOCK).map(|i| (i % 251) as u8).collect()),
+    ];
+    for block in &adversarial {
+        assert!(parity_even(block));
+        let encoded = encode(block);
+        assert_eq!(flags(&encoded), [0], "nonzero block must not be skipped");
+        assert_eq!(&decode(&encoded).unwrap(), block);
+    }
+}
+
+#[test]
+fn near_repeat_is_not_skipped() {
+    let first = random_bytes(BLOCK, 9);
+    let mut second = first.clone();
+    second[BLOCK - 1] ^= 2;
+    let second = force_even(second);
+    assert_ne!(first, second);
+    let src = [first, second].concat();
+    let encoded = encode(&src);
+    assert_eq!(flags(&encoded), [0, 0]);
+    assert_eq!(decode(&encoded).unwrap(), src);
+}
+
+#[test]
+fn zero_and_repeat_blocks_are_skipped() {
+    let pattern = random_bytes(BLOCK, 5);
+    let src = [vec![0; BLOCK], pattern.clone(), pattern.clone(), vec![0; 100]].concat();
+    let encoded = encode(&src);
+    assert_eq!(flags(&encoded), [FLAG_PARITY, 0, FLAG_PARITY | FLAG_REPEAT, FLAG_PARITY]);
+    assert_eq!(decode(&encoded).unwrap(), src);
+}
+
+#[test]
+fn short_tail_does_not_repeat_longer_block() {
+    let src = [vec![7u8; BLOCK], vec![7u8; 10]].concat();
+    let encoded = encode(&src);
+    assert_eq!(flags(&encoded), [0, 0]);
+    assert_eq!(decode(&encoded).unwrap(), src);
+}
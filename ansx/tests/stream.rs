@@ -0,0 +1,150 @@
+use ansx::{AnsxError, DecodeOptions, Decoder, EncodeOptions, Encoder, Interleave, Model, decode, encode, encode_with};
+use std::io::{self, Read, Write};
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+/// Mixed content: skewed, zero and repeated blocks, and an odd-sized tail.
+fn sample(len: usize) -> Vec<u8> {
+    let mut data: Vec<u8> = random_bytes(len, 1).iter().map(|b| b.leading_zeros() as u8).collect();
+    let end = len.min(40_000);
+    data[20_000.min(end)..end].fill(0);
+    data
+}
+
+/// Writes `data` through an [`Encoder`] in pieces of `piece` bytes.
+fn stream_encode(data: &[u8], piece: usize, options: EncodeOptions) -> Vec<u8> {
+    let mut enc = Encoder::with_options(Vec::new(), options);
+    for part in data.chunks(piece) {
+        enc.write_all(part).unwrap();
+    }
+    enc.finish().unwrap()
+}
+
+/// Reader handing out at most one byte per call.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.0.len().min(buf.len()).min(1);
+        buf[..n].copy_from_slice(&self.0[..n]);
+        self.0 = &self.0[n..];
+        Ok(n)
+    }
+}
+
+#[test]
+fn encoder_matches_one_shot_encode() {
+    let options = [
+        EncodeOptions::new(),
+        EncodeOptions::new().interleave(Interleave::Four).model(Model::Adaptive),
+        EncodeOptions::new().checksum(false),
+    ];
+    for len in [0, 1, 16_383, 16_384, 16_385, 100_001] {
+        let data = sample(len);
+        for opts in options {
+            let expected = encode_with(&data, &opts);
+            for piece in [1, 1000, 16_384, 70_000] {
+                assert_eq!(stream_encode(&data, piece, opts), expected, "{len} bytes in {piece}-byte pieces");
+            }
+        }
+    }
+}
+
+#[test]
+fn decoder_roundtrips_in_pieces() {
+    let data = sample(100_001);
+    let encoded = encode(&data);
+    for piece in [1, 7, 4096, 50_000] {
+        let mut dec = Decoder::new(&encoded[..]);
+        let mut out = Vec::new();
+        let mut buf = vec![0; piece];
+        loop {
+            let n = dec.read(&mut buf).unwrap();
+            if n == 0 {
+                break;
+            }
+            out.extend_from_slice(&buf[..n]);
+        }
+        assert_eq!(out, data, "{piece}-byte reads");
+    }
+
+    let mut out = Vec::new();
+    Decoder::new(Trickle(&encoded)).read_to_end(&mut out).unwrap();
+    assert_eq!(out, data);
+}
+
+#[test]
+fn pipeline_streams_large_input() {
+    // 8 MiB through encoder and decoder without holding the input.
+    let source = io::repeat(0x5A).take(4 << 20).chain(io::repeat(0).take(4 << 20));
+    let mut enc = Encoder::new(Vec::new());
+    assert_eq!(io::copy(&mut { source }, &mut enc).unwrap(), 8 << 20);
+    let encoded = enc.finish().unwrap();
+    assert!(encoded.len() < 64 << 10);
+
+    let mut dec = Decoder::new(&encoded[..]);
+    let mut total = 0u64;
+    let mut buf = [0u8; 10_000];
+    loop {
+        let n = dec.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        for &b in &buf[..n] {
+            assert_eq!(b, if total < 4 << 20 { 0x5A } else { 0 });
+            total += 1;
+        }
+    }
+    assert_eq!(total, 8 << 20);
+}
+
+#[test]
+fn flush_makes_written_data_decodable() {
+    let data = sample(30_000);
+    let mut enc = Encoder::new(Vec::new());
+    enc.write_all(&data[..10_000]).unwrap();
+    enc.flush().unwrap();
+    assert_eq!(decode(enc.get_ref()).unwrap(), &data[..10_000]);
+    enc.write_all(&data[10_000..]).unwrap();
+    let encoded = enc.finish().unwrap();
+    assert_eq!(decode(&encoded).unwrap(), data);
+}
+
+#[test]
+fn decoder_errors_are_typed_and_sticky() {
+    let encoded = encode(&sample(40_000));
+    let mut out = Vec::new();
+    let err = Decoder::new(&encoded[..encoded.len() - 3]).read_to_end(&mut out).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+
+    let mut bad = encoded.clone();
+    bad[0] = b'Z';
+    let mut dec = Decoder::new(&bad[..]);
+    let mut buf = [0u8; 16];
+    for _ in 0..2 {
+        let err = dec.read(&mut buf).unwrap_err();
+        assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        assert_eq!(err.into_inner().unwrap().downcast_ref::<AnsxError>(), Some(&AnsxError::BadHeader));
+    }
+}
+
+#[test]
+fn decoder_enforces_output_limit() {
+    let data = sample(40_000);
+    let encoded = encode(&data);
+    let mut out = Vec::new();
+    let limited = DecodeOptions::new().max_output(30_000);
+    let err = Decoder::with_options(&encoded[..], limited).read_to_end(&mut out).unwrap_err();
+    assert_eq!(err.into_inner().unwrap().downcast_ref::<AnsxError>(), Some(&AnsxError::OutputTooLarge));
+    // The first block fits; the second would cross the limit.
+    assert_eq!(out, &data[..16_384]);
+}
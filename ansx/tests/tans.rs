@@ -0,0 +1,140 @@
+use ansx::bitio::BitWriter;
+use ansx::chunk::{self, BACKEND_RANS, BACKEND_TANS};
+use ansx::tans::{self, TABLE_LOG, TansTable};
+use ansx::*;
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+fn skewed(len: usize, seed: u64) -> Vec<u8> {
+    random_bytes(len, seed).iter().map(|b| b.leading_zeros() as u8).collect()
+}
+
+fn tans() -> EncodeOptions {
+    EncodeOptions::new().backend(Backend::Tans)
+}
+
+fn backends(encoded: &[u8]) -> Vec<u8> {
+    chunk::chunks(encoded).map(|c| c.unwrap().header.backend).collect()
+}
+
+#[test]
+fn roundtrips() {
+    let inputs = [
+        Vec::new(),
+        b"abracadabra".to_vec(),
+        vec![7; 1000],
+        skewed(100_000, 1),
+        random_bytes(70_000, 2),
+        skewed(50_000, 3).iter().map(|b| b.wrapping_mul(37)).collect(),
+    ];
+    for data in &inputs {
+        let encoded = encode_with(data, &tans());
+        assert_eq!(&decode(&encoded).unwrap(), data, "len {}", data.len());
+    }
+}
+
+#[test]
+fn backend_is_recorded_per_chunk() {
+    // Skewed, then random (stored), then zeros (skipped).
+    let mut data = skewed(16_384, 4);
+    data.extend(random_bytes(16_384, 5));
+    data.extend([0; 16_384]);
+    let encoded = encode_with(&data, &tans().interleave(Interleave::Eight));
+    assert_eq!(backends(&encoded), [BACKEND_TANS, BACKEND_RANS, BACKEND_RANS]);
+    let lanes: Vec<u8> = chunk::chunks(&encoded).map(|c| c.unwrap().header.lanes).collect();
+    assert_eq!(lanes, [0, 0, 0]);
+    assert_eq!(decode(&encoded).unwrap(), data);
+
+    let adaptive = encode_with(&data, &tans().model(Model::Adaptive));
+    assert!(backends(&adaptive).iter().all(|&b| b == BACKEND_RANS));
+}
+
+#[test]
+fn size_is_close_to_rans() {
+    let data = skewed(1 << 20, 6);
+    let rans = encode(&data).len();
+    let tans = encode_with(&data, &tans()).len();
+    println!("rANS {rans} B, tANS {tans} B");
+    assert!(tans < rans + rans / 50, "tANS {tans} vs rANS {rans}");
+}
+
+#[test]
+fn table_serialization_roundtrips() {
+    let mut counts = [0u32; 256];
+    for &b in &skewed(16_384, 7) {
+        counts[b as usize * 13] += 1;
+    }
+    counts[255] = 1;
+    let table = TansTable::from_counts(&counts, TABLE_LOG).unwrap();
+    let bytes = tans::encode_table(&table);
+    let (back, used) = tans::decode_table(&bytes).unwrap();
+    assert_eq!(back, table);
+    assert_eq!(used, bytes.len());
+    assert!(bytes.len() < 32, "{} bytes", bytes.len());
+
+    let single = TansTable::from_counts(&[0, 0, 9], 5).unwrap();
+    assert_eq!(single.freqs(), [0, 0, 32]);
+    let stream = tans::encode(&single, &[2; 100]);
+    assert_eq!(stream.len(), 1, "a certain symbol costs no bits");
+    let mut out = Vec::new();
+    tans::decode(&single, &stream, 100, &mut out).unwrap();
+    assert_eq!(out, [2; 100]);
+}
+
+#[test]
+fn rejects_malformed_tables() {
+    assert_eq!(tans::decode_table(&[]).map(|_| ()), Err(AnsxError::Truncated));
+    // Table log outside the accepted range.
+    let mut w = BitWriter::new();
+    w.write_gamma(1);
+    w.write_bits(13, 4);
+    w.write_gamma(8193);
+    assert_eq!(tans::decode_table(&w.finish()).map(|_| ()), Err(AnsxError::BadHeader));
+    // Frequencies overshooting the table size.
+    let mut w = BitWriter::new();
+    w.write_gamma(2);
+    w.write_bits(5, 4);
+    w.write_gamma(20);
+    w.write_gamma(20);
+    assert_eq!(tans::decode_table(&w.finish()).map(|_| ()), Err(AnsxError::BadHeader));
+    // More symbols than the declared alphabet.
+    let mut w = BitWriter::new();
+    w.write_gamma(1);
+    w.write_bits(5, 4);
+    w.write_gamma(17);
+    w.write_gamma(17);
+    assert_eq!(tans::decode_table(&w.finish()).map(|_| ()), Err(AnsxError::BadHeader));
+}
+
+#[test]
+fn rejects_damaged_streams() {
+    let data = skewed(10_000, 8);
+    let table = {
+        let mut counts = [0u32; 256];
+        for &b in &data {
+            counts[b as usize] += 1;
+        }
+        TansTable::from_counts(&counts, TABLE_LOG).unwrap()
+    };
+    let stream = tans::encode(&table, &data);
+    let mut out = Vec::new();
+    assert_eq!(tans::decode(&table, &stream[..stream.len() - 8], data.len(), &mut out), Err(AnsxError::Truncated));
+    out.clear();
+    assert_eq!(tans::decode(&table, &stream, data.len() - 1, &mut out), Err(AnsxError::Corrupt));
+
+    let encoded = encode_with(&data, &tans());
+    for i in [30, encoded.len() / 2, encoded.len() - 5] {
+        let mut bad = encoded.clone();
+        bad[i] ^= 0x10;
+        assert!(decode(&bad).is_err(), "flip at {i}");
+    }
+}
@@ -0,0 +1,126 @@
+use ansx::bitio::{BitReader, BitWriter};
+use ansx::rans::{FreqTable, PROB_SCALE};
+use ansx::zeta::{decode_table, encode_table, read_table, write_table};
+use ansx::{AnsxError, BlockParams, GaussianTableBuilder};
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8
+        })
+        .collect()
+}
+
+fn table_of(bytes: &[u8]) -> FreqTable {
+    let mut counts = [0u32; 256];
+    for &b in bytes {
+        counts[b as usize] += 1;
+    }
+    FreqTable::from_counts(&counts).unwrap()
+}
+
+fn sparse() -> FreqTable {
+    table_of(&random_bytes(16_384, 1).iter().map(|b| b.leading_zeros() as u8 * 31).collect::<Vec<_>>())
+}
+
+fn dense() -> FreqTable {
+    table_of(&random_bytes(16_384, 2))
+}
+
+fn gaussian(sigma: f32) -> FreqTable {
+    let params = BlockParams { mu: 32_768.0, sigma };
+    GaussianTableBuilder::new().build(params).unwrap().freq_table().clone()
+}
+
+/// Bytes the table would take as a flat array of 16-bit `freq - 1` values.
+fn flat_size(table: &FreqTable) -> usize {
+    2 * table.len()
+}
+
+#[test]
+fn reports_table_overhead() {
+    let cases = [
+        ("sparse", sparse()),
+        ("dense", dense()),
+        ("gaussian σ=4", gaussian(4.0)),
+        ("gaussian σ=300", gaussian(300.0)),
+    ];
+    println!("{:<16} {:>7} {:>6} {:>8} {:>7}", "table", "symbols", "used", "flat B", "ζ B");
+    for (name, table) in &cases {
+        let bytes = encode_table(table);
+        let used = table.freqs().iter().filter(|&&f| f > 0).count();
+        println!("{name:<16} {:>7} {used:>6} {:>8} {:>7}", table.len(), flat_size(table), bytes.len());
+
+        let (back, consumed) = decode_table(&bytes).unwrap();
+        assert_eq!(&back, table, "{name}");
+        assert_eq!(consumed, bytes.len());
+        assert!(bytes.len() < flat_size(table), "{name}: {} bytes", bytes.len());
+    }
+    // Nine used symbols out of 256 cost a few dozen bytes, not a 512-byte array.
+    assert!(encode_table(&cases[0].1).len() <= 32);
+}
+
+#[test]
+fn tables_share_a_bit_stream() {
+    let mut w = BitWriter::new();
+    w.write_bits(0b101, 3);
+    write_table(&sparse(), &mut w);
+    write_table(&gaussian(50.0), &mut w);
+    w.write_gamma(77);
+    let bytes = w.finish();
+
+    let mut r = BitReader::new(&bytes);
+    assert_eq!(r.read_bits(3).unwrap(), 0b101);
+    assert_eq!(read_table(&mut r).unwrap(), sparse());
+    assert_eq!(read_table(&mut r).unwrap(), gaussian(50.0));
+    assert_eq!(r.read_gamma().unwrap(), 77);
+}
+
+#[test]
+fn single_symbol_table() {
+    let mut freq = vec![0; 256];
+    freq[200] = PROB_SCALE;
+    let table = FreqTable::from_freqs(freq).unwrap();
+    let bytes = encode_table(&table);
+    assert_eq!(decode_table(&bytes).unwrap().0, table);
+}
+
+#[test]
+fn rejects_malformed_tables() {
+    let bytes = encode_table(&dense());
+    for cut in [0, 1, bytes.len() / 2, bytes.len() - 1] {
+        assert_eq!(decode_table(&bytes[..cut]).map(|_| ()), Err(AnsxError::Truncated), "cut at {cut}");
+    }
+
+    // Width field above 16 bits.
+    let mut w = BitWriter::new();
+    w.write_gamma(256);
+    w.write_bit(false);
+    w.write_bits(17, 5);
+    w.write_bits(0, 32);
+    assert_eq!(decode_table(&w.finish()).map(|_| ()), Err(AnsxError::BadHeader));
+
+    // Dense table whose frequencies overshoot PROB_SCALE.
+    let mut w = BitWriter::new();
+    w.write_gamma(2);
+    w.write_bit(false);
+    w.write_bits(16, 5);
+    w.write_bit(true);
+    w.write_bits(40_000, 16);
+    w.write_bit(true);
+    w.write_bits(40_000, 16);
+    assert_eq!(decode_table(&w.finish()).map(|_| ()), Err(AnsxError::BadHeader));
+
+    // Sparse gap that runs past the alphabet.
+    let mut w = BitWriter::new();
+    w.write_gamma(4);
+    w.write_bit(true);
+    w.write_bits(16, 5);
+    w.write_gamma(9);
+    w.write_bit(true);
+    w.write_bits(PROB_SCALE - 1, 16);
+    assert_eq!(decode_table(&w.finish()).map(|_| ()), Err(AnsxError::BadHeader));
+}
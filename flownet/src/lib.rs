@@ -388,6 +388,8 @@ impl FlowNet {
         Self { _dummy: 0 }
     }
     
+    // Inherent constructor kept for existing callers of `FlowNet::default()`.
+    #[allow(clippy::should_implement_trait)]
     pub fn default() -> Self {
         Self::new(4, 4)
     }
@@ -411,6 +413,9 @@ pub extern "C" fn flownet_new(levels: usize, depth: usize) -> *mut FlowNet {
     Box::into_raw(Box::new(FlowNet::new(levels, depth)))
 }
 
+// Safe signature kept for existing callers; `ptr` must be null or an unfreed
+// pointer from `flownet_new`.
+#[allow(clippy::not_unsafe_ptr_arg_deref)]
 #[no_mangle]
 pub extern "C" fn flownet_free(ptr: *mut FlowNet) {
     if !ptr.is_null() {
@@ -22,7 +22,16 @@ Ledger encode(const std::vector<uint8_t>& data);
 std::vector<uint8_t> decode(const Ledger& ledger);
 
 // Helper utilities to read/write Ledger to a binary buffer so that it can be stored to disk.
+// serialize() writes format v1: [num_runs:u32][count:u32][value:u8]... (all little-endian).
 std::vector<uint8_t> serialize(const Ledger& ledger);
+
+// Format v2: "LDG\xFF", version byte 2, LEB128 num_runs, LEB128 counts, then the values.
+std::vector<uint8_t> serialize_v2(const Ledger& ledger);
+
+// Whichever of v1 and v2 is shorter (v1 on a tie).
+std::vector<uint8_t> serialize_smallest(const Ledger& ledger);
+
+// Reads either format; v2 is recognised by its magic.
 Ledger deserialize(const std::vector<uint8_t>& buffer);
 
 } // namespace ledgerizer 
\ No newline at end of file
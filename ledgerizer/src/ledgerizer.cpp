@@ -1,10 +1,91 @@
 #include "ledgerizer/ledgerizer.h"
 
+#include <algorithm>
 #include <limits>
 #include <stdexcept>
+#include <string>
 
 namespace ledgerizer {
 
+namespace {
+
+constexpr uint8_t MAGIC_V2[4] = {'L', 'D', 'G', 0xFF};
+constexpr uint8_t VERSION_2 = 2;
+
+size_t leb128_len(uint32_t v) {
+    size_t n = 1;
+    while (v >= 0x80) {
+        v >>= 7;
+        ++n;
+    }
+    return n;
+}
+
+void write_leb128(std::vector<uint8_t>& buffer, uint32_t v) {
+    while (v >= 0x80) {
+        buffer.push_back(static_cast<uint8_t>(v | 0x80));
+        v >>= 7;
+    }
+    buffer.push_back(static_cast<uint8_t>(v));
+}
+
+// Rejects values above UINT32_MAX and padded encodings.
+uint32_t read_leb128(const std::vector<uint8_t>& buffer, size_t& offset) {
+    uint32_t v = 0;
+    for (int i = 0; i < 5; ++i) {
+        if (offset >= buffer.size()) {
+            throw std::runtime_error("Malformed Ledger buffer (varint)");
+        }
+        uint8_t b = buffer[offset++];
+        uint32_t bits = b & 0x7F;
+        if (i == 4 && bits > 0x0F) {
+            break;
+        }
+        v |= bits << (7 * i);
+        if ((b & 0x80) == 0) {
+            if (b == 0 && i > 0) {
+                break;
+            }
+            return v;
+        }
+    }
+    throw std::runtime_error("Malformed Ledger buffer (varint)");
+}
+
+size_t serialized_len_v2(const Ledger& ledger) {
+    size_t len = sizeof(MAGIC_V2) + 1 + leb128_len(static_cast<uint32_t>(ledger.runs.size()));
+    for (const auto& [count, value] : ledger.runs) {
+        len += leb128_len(count) + 1;
+    }
+    return len;
+}
+
+Ledger deserialize_v2(const std::vector<uint8_t>& buffer) {
+    Ledger ledger;
+    size_t offset = sizeof(MAGIC_V2);
+    if (offset >= buffer.size()) {
+        throw std::runtime_error("Buffer too small to contain Ledger");
+    }
+    uint8_t version = buffer[offset++];
+    if (version != VERSION_2) {
+        throw std::runtime_error("Unsupported Ledger format version " + std::to_string(version));
+    }
+    uint32_t num_runs = read_leb128(buffer, offset);
+    std::vector<uint32_t> counts;
+    for (uint32_t i = 0; i < num_runs; ++i) {
+        counts.push_back(read_leb128(buffer, offset));
+    }
+    if (buffer.size() - offset != num_runs) {
+        throw std::runtime_error("Malformed Ledger buffer (values)");
+    }
+    for (uint32_t i = 0; i < num_runs; ++i) {
+        ledger.runs.emplace_back(counts[i], buffer[offset++]);
+    }
+    return ledger;
+}
+
+} // unnamed namespace
+
 Ledger encode(const std::vector<uint8_t>& data) {
     Ledger ledger;
     if (data.empty()) {
@@ -55,7 +136,31 @@ std::vector<uint8_t> serialize(const Ledger& ledger) {
     return buffer;
 }
 
+std::vector<uint8_t> serialize_v2(const Ledger& ledger) {
+    std::vector<uint8_t> buffer;
+    buffer.reserve(serialized_len_v2(ledger));
+    buffer.insert(buffer.end(), MAGIC_V2, MAGIC_V2 + sizeof(MAGIC_V2));
+    buffer.push_back(VERSION_2);
+    write_leb128(buffer, static_cast<uint32_t>(ledger.runs.size()));
+    for (const auto& [count, value] : ledger.runs) {
+        write_leb128(buffer, count);
+    }
+    for (const auto& [count, value] : ledger.runs) {
+        buffer.push_back(value);
+    }
+    return buffer;
+}
+
+std::vector<uint8_t> serialize_smallest(const Ledger& ledger) {
+    size_t v1_len = sizeof(uint32_t) + ledger.runs.size() * (sizeof(uint32_t) + sizeof(uint8_t));
+    return serialized_len_v2(ledger) < v1_len ? serialize_v2(ledger) : serialize(ledger);
+}
+
 Ledger deserialize(const std::vector<uint8_t>& buffer) {
+    if (buffer.size() >= sizeof(MAGIC_V2) && std::equal(MAGIC_V2, MAGIC_V2 + sizeof(MAGIC_V2), buffer.begin())) {
+        return deserialize_v2(buffer);
+    }
+
     Ledger ledger;
     if (buffer.size() < sizeof(uint32_t)) {
         throw std::runtime_error("Buffer too small to contain Ledger");
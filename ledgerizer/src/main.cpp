@@ -24,8 +24,9 @@ void write_file(const std::string& path, const std::vector<uint8_t>& data) {
 }
 
 void print_usage(const char* exe) {
-    std::cerr << "Usage: " << exe << " [c|d] <input> <output>\n";
-    std::cerr << "  c: compress (encode)\n";
+    std::cerr << "Usage: " << exe << " [c|c2|d] <input> <output>\n";
+    std::cerr << "  c: compress (encode), ledger format v1\n";
+    std::cerr << "  c2: compress (encode), ledger format v2\n";
     std::cerr << "  d: decompress (decode)\n";
 }
 
@@ -41,10 +42,10 @@ int main(int argc, char** argv) {
     std::string output_path = argv[3];
 
     try {
-        if (mode == "c") {
+        if (mode == "c" || mode == "c2") {
             auto data = read_file(input_path);
             auto ledger = ledgerizer::encode(data);
-            auto buffer = ledgerizer::serialize(ledger);
+            auto buffer = mode == "c" ? ledgerizer::serialize(ledger) : ledgerizer::serialize_v2(ledger);
             write_file(output_path, buffer);
         } else if (mode == "d") {
             auto buffer = read_file(input_path);
@@ -17,6 +17,15 @@ int main() {
 
     assert(original == recovered2 && "Serialize/deserialize failed");
 
+    auto buffer_v2 = ledgerizer::serialize_v2(ledger);
+    assert(buffer_v2.size() < buffer.size() && "v2 should be smaller for short runs");
+    auto recovered3 = ledgerizer::decode(ledgerizer::deserialize(buffer_v2));
+
+    assert(original == recovered3 && "Serialize v2/deserialize failed");
+    assert(ledgerizer::serialize_smallest(ledger) == buffer_v2 && "serialize_smallest should pick v2");
+    assert(ledgerizer::serialize_smallest(ledgerizer::Ledger{}) == ledgerizer::serialize(ledgerizer::Ledger{}) &&
+           "serialize_smallest should pick v1 for an empty ledger");
+
     std::cout << "All ledgerizer basic tests passed.\n";
     return 0;
 } 
\ No newline at end of file
@@ -0,0 +1,74 @@
+//! Context mixing (`Mode::HighRatio`) versus the run-length ledger path, with
+//! the LZ77 parse stage (`LzCoder`, no entropy coding) for reference.
+//!
+//! `cargo bench -p rbtcore --bench cm` prints size and throughput per mode on
+//! a text corpus (the repository's Markdown files), a binary corpus (this
+//! bench's own executable) and run-heavy data, each up to 1 MiB (set
+//! `RBT_BENCH_KB` to change the size).
+
+use rbtcore::alpha_flow::{AlphaFlowEncode, Mode};
+use rbtcore::lz77::LzCoder;
+use std::path::Path;
+use std::time::Instant;
+
+/// Best of `runs` timings of `f`, in seconds.
+fn best<T>(runs: usize, mut f: impl FnMut() -> T) -> f64 {
+    (0..runs)
+        .map(|_| {
+            let start = Instant::now();
+            std::hint::black_box(f());
+            start.elapsed().as_secs_f64()
+        })
+        .fold(f64::INFINITY, f64::min)
+}
+
+/// Markdown files of the workspace, up to `len` bytes.
+fn text_corpus(len: usize) -> Vec<u8> {
+    let root = Path::new(env!("CARGO_MANIFEST_DIR")).join("..");
+    let mut files: Vec<_> = [root.clone(), root.join("docs"), root.join("ledgerizer")]
+        .iter()
+        .flat_map(|dir| std::fs::read_dir(dir).unwrap())
+        .map(|e| e.unwrap().path())
+        .filter(|p| p.extension().is_some_and(|e| e == "md"))
+        .collect();
+    files.sort();
+    let mut text: Vec<u8> = files.iter().flat_map(|p| std::fs::read(p).unwrap()).collect();
+    text.truncate(len);
+    text
+}
+
+fn main() {
+    let kb: usize = std::env::var("RBT_BENCH_KB").ok().and_then(|v| v.parse().ok()).unwrap_or(1024);
+    let len = kb << 10;
+    let mut binary = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+    binary.truncate(len);
+    let mut seed = 1u32;
+    let runs: Vec<u8> = (0..len / 16)
+        .flat_map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            std::iter::repeat_n((seed >> 29) as u8, 16)
+        })
+        .collect();
+    let inputs = [("text", text_corpus(len)), ("binary", binary), ("runs", runs)];
+    let modes: [(&str, &dyn AlphaFlowEncode); 3] =
+        [("ledger", &Mode::Ledger), ("cm", &Mode::HighRatio), ("lz77", &LzCoder::new())];
+
+    println!("{:<8} {:>8} {:<8} {:>7} {:>6} {:>10} {:>10}", "data", "bytes", "mode", "ratio", "bpc", "enc MB/s", "dec MB/s");
+    for (name, data) in &inputs {
+        for (mode_name, mode) in &modes {
+            let encoded = mode.encode(data);
+            assert_eq!(&mode.decode(&encoded).unwrap(), data);
+            let enc_s = best(2, || mode.encode(data));
+            let dec_s = best(2, || mode.decode(&encoded).unwrap());
+            let mbps = |secs: f64| data.len() as f64 / secs / 1e6;
+            let ratio = encoded.len() as f64 / data.len() as f64;
+            println!(
+                "{name:<8} {:>8} {mode_name:<8} {ratio:>7.4} {:>6.3} {:>10.2} {:>10.2}",
+                data.len(),
+                ratio * 8.0,
+                mbps(enc_s),
+                mbps(dec_s)
+            );
+        }
+    }
+}
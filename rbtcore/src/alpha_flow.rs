@@ -1,14 +1,33 @@
-//! α-Flow entropy coder traits and placeholder implementation.
+//! α-Flow entropy coder traits and implementations.
+
+use crate::beta_context::cm::CmCoder;
+use crate::error::{CodecError, Result};
+use crate::ledger::{self, ReadOptions};
 
 /// Trait for types that can encode/decode a byte slice using α-Flow.
 ///
-/// Implementations must be *loss-less*: `decode(encode(data)) == data`.
+/// Implementations must be *loss-less*: `decode(encode(data)) == Ok(data)`.
 pub trait AlphaFlowEncode {
     /// Encode raw bytes into compressed representation.
     fn encode(&self, input: &[u8]) -> Vec<u8>;
 
     /// Decode from compressed representation into raw bytes.
-    fn decode(&self, compressed: &[u8]) -> Vec<u8>;
+    ///
+    /// Fails with a [`crate::error::CodecError`] instead of panicking when
+    /// `compressed` is truncated, corrupt or otherwise unreadable.
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>>;
+
+    /// [`AlphaFlowEncode::decode`] that fails with
+    /// [`CodecError::LimitExceeded`] instead of producing more than `max_len`
+    /// bytes.
+    ///
+    /// The default decodes in full and then checks the length; coders whose
+    /// output size is declared in their input override it to fail before
+    /// allocating.
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>> {
+        let out = self.decode(compressed)?;
+        if out.len() as u64 > max_len { Err(CodecError::LimitExceeded) } else { Ok(out) }
+    }
 }
 
 /// No-op reference implementation used for unit tests.
@@ -20,7 +39,143 @@ impl AlphaFlowEncode for NoopCoder {
         input.to_vec()
     }
 
-    fn decode(&self, compressed: &[u8]) -> Vec<u8> {
-        compressed.to_vec()
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        Ok(compressed.to_vec())
+    }
+
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>> {
+        if compressed.len() as u64 > max_len {
+            return Err(CodecError::LimitExceeded);
+        }
+        self.decode(compressed)
+    }
+}
+
+/// Run-length ledger coder, the `ledgerizer` path: [`ledger::encode`] written
+/// with [`ledger::serialize_smallest`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct LedgerCoder {
+    options: ReadOptions,
+}
+
+impl LedgerCoder {
+    /// Creates a coder that decodes without caps.
+    pub fn new() -> Self {
+        Self::with_options(ReadOptions::new())
     }
-} 
\ No newline at end of file
+
+    /// Creates a coder that decodes under `options`.
+    pub fn with_options(options: ReadOptions) -> Self {
+        Self { options }
+    }
+}
+
+impl AlphaFlowEncode for LedgerCoder {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        ledger::serialize_smallest(&ledger::encode(input))
+    }
+
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        let ledger = ledger::deserialize_with(compressed, &self.options)?;
+        Ok(ledger::decode_with(&ledger, &self.options)?)
+    }
+
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>> {
+        let options = self.options.max_output(self.options.max_output.min(max_len));
+        LedgerCoder::with_options(options).decode(compressed)
+    }
+}
+
+const MODE_LEDGER: u8 = 0;
+const MODE_HIGH_RATIO: u8 = 1;
+
+/// Coder chosen at encode time. The output starts with a byte naming the
+/// mode, so decoding works whichever variant decodes it.
+///
+/// Decoding is uncapped; use [`ModeCoder`] for input that may be hostile.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    /// Fast run-length ledger, [`LedgerCoder`].
+    #[default]
+    Ledger,
+    /// Context mixing with arithmetic coding, [`CmCoder`]: much smaller
+    /// output at around 1 MB/s.
+    HighRatio,
+}
+
+impl AlphaFlowEncode for Mode {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        ModeCoder::new(*self).encode(input)
+    }
+
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        ModeCoder::new(*self).decode(compressed)
+    }
+
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>> {
+        ModeCoder::new(*self).decode_capped(compressed, max_len)
+    }
+}
+
+/// [`Mode`] with decoding limits, which apply whichever mode the input
+/// names.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ModeCoder {
+    mode: Mode,
+    ledger: ReadOptions,
+    max_output: u64,
+}
+
+impl Default for ModeCoder {
+    fn default() -> Self {
+        Self::new(Mode::default())
+    }
+}
+
+impl ModeCoder {
+    /// Creates a coder that encodes with `mode` and decodes without caps.
+    pub fn new(mode: Mode) -> Self {
+        Self { mode, ledger: ReadOptions::new(), max_output: u64::MAX }
+    }
+
+    /// Caps for ledger payloads.
+    pub fn ledger_options(mut self, options: ReadOptions) -> Self {
+        self.ledger = options;
+        self
+    }
+
+    /// Fails with [`CodecError::LimitExceeded`] instead of decoding more than
+    /// `limit` bytes, in either mode.
+    pub fn max_output(mut self, limit: u64) -> Self {
+        self.max_output = limit;
+        self
+    }
+}
+
+impl AlphaFlowEncode for ModeCoder {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        let (tag, body) = match self.mode {
+            Mode::Ledger => (MODE_LEDGER, LedgerCoder::new().encode(input)),
+            Mode::HighRatio => (MODE_HIGH_RATIO, CmCoder::new().encode(input)),
+        };
+        let mut out = Vec::with_capacity(1 + body.len());
+        out.push(tag);
+        out.extend_from_slice(&body);
+        out
+    }
+
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        self.decode_capped(compressed, u64::MAX)
+    }
+
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>> {
+        let limit = self.max_output.min(max_len);
+        let ledger = LedgerCoder::with_options(self.ledger);
+        match compressed.split_first() {
+            Some((&MODE_LEDGER, body)) => ledger.decode_capped(body, limit),
+            Some((&MODE_HIGH_RATIO, body)) => CmCoder::new().decode_capped(body, limit),
+            Some((&tag, _)) => Err(CodecError::UnsupportedVersion(tag)),
+            None => Err(CodecError::Truncated),
+        }
+    }
+}
@@ -0,0 +1,113 @@
+//! Binary arithmetic coder driven by 12-bit probabilities.
+//!
+//! Each call codes one bit with `p`, the probability that the bit is 1, in
+//! units of 1/4096. The coder keeps a 32-bit range `[x1, x2]` and shifts out
+//! a byte whenever the top bytes of both ends agree, so it never carries.
+//! The encoder flushes four bytes at the end; the decoder treats bytes past
+//! the end of its input as zeros and reports whether it needed any.
+
+/// Bits of probability precision.
+pub const PROB_BITS: u32 = 12;
+
+/// Narrows `[x1, x2]` to the half of it that `bit` selects under `p`.
+#[inline]
+fn split(x1: u32, x2: u32, p: u32) -> u32 {
+    debug_assert!(0 < p && p < 1 << PROB_BITS, "probability {p} out of range");
+    let range = x2 - x1;
+    x1 + (range >> PROB_BITS) * p + (((range & ((1 << PROB_BITS) - 1)) * p) >> PROB_BITS)
+}
+
+/// Arithmetic encoder appending to a byte vector.
+#[derive(Debug, Clone)]
+pub struct Encoder {
+    x1: u32,
+    x2: u32,
+    out: Vec<u8>,
+}
+
+impl Default for Encoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Encoder {
+    /// Creates an encoder with the full range.
+    pub fn new() -> Self {
+        Self { x1: 0, x2: u32::MAX, out: Vec::new() }
+    }
+
+    /// Codes `bit` (0 or 1) given `p`, the probability of a 1 in `1..4096`.
+    #[inline]
+    pub fn encode(&mut self, bit: u8, p: u32) {
+        let mid = split(self.x1, self.x2, p);
+        if bit != 0 {
+            self.x2 = mid;
+        } else {
+            self.x1 = mid + 1;
+        }
+        while (self.x1 ^ self.x2) & 0xff00_0000 == 0 {
+            self.out.push((self.x2 >> 24) as u8);
+            self.x1 <<= 8;
+            self.x2 = self.x2 << 8 | 0xff;
+        }
+    }
+
+    /// Flushes the range and returns the coded bytes.
+    pub fn finish(mut self) -> Vec<u8> {
+        self.out.extend_from_slice(&self.x1.to_be_bytes());
+        self.out
+    }
+}
+
+/// Arithmetic decoder over a stream written by [`Encoder`].
+#[derive(Debug, Clone)]
+pub struct Decoder<'a> {
+    x1: u32,
+    x2: u32,
+    x: u32,
+    buf: &'a [u8],
+    pos: usize,
+}
+
+impl<'a> Decoder<'a> {
+    /// Starts decoding `buf`.
+    pub fn new(buf: &'a [u8]) -> Self {
+        let mut d = Self { x1: 0, x2: u32::MAX, x: 0, buf, pos: 0 };
+        for _ in 0..4 {
+            d.x = d.x << 8 | d.next_byte();
+        }
+        d
+    }
+
+    /// Decodes one bit coded with the same `p` the encoder used.
+    #[inline]
+    pub fn decode(&mut self, p: u32) -> u8 {
+        let mid = split(self.x1, self.x2, p);
+        let bit = self.x <= mid;
+        if bit {
+            self.x2 = mid;
+        } else {
+            self.x1 = mid + 1;
+        }
+        while (self.x1 ^ self.x2) & 0xff00_0000 == 0 {
+            self.x1 <<= 8;
+            self.x2 = self.x2 << 8 | 0xff;
+            self.x = self.x << 8 | self.next_byte();
+        }
+        bit as u8
+    }
+
+    /// Returns `true` if decoding has read past the end of the input, which
+    /// an untruncated stream never needs.
+    pub fn overran(&self) -> bool {
+        self.pos > self.buf.len()
+    }
+
+    #[inline]
+    fn next_byte(&mut self) -> u32 {
+        let b = self.buf.get(self.pos).copied().unwrap_or(0);
+        self.pos += 1;
+        b as u32
+    }
+}
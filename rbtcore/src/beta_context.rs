@@ -1,5 +1,266 @@
-//! β-Context modelling placeholder.
+//! β-Context modelling: byte predictions from the preceding bytes.
+//!
+//! A [`ContextModel`] hands an entropy coder a [`Distribution`] for the next
+//! byte, then learns from the byte actually coded. Encoder and decoder run
+//! the same sequence of [`ContextModel::predict`] and [`ContextModel::update`]
+//! calls, so they see identical distributions; all arithmetic is integer, so
+//! that holds across platforms too.
+//!
+//! [`BetaContext`] blends order-0, order-1 and order-2 byte contexts. Each
+//! order keeps adaptive counts per context: one table for order 0, one per
+//! previous byte for order 1, and for order 2 a table chosen by a hash of the
+//! previous two bytes. Orders with more observations in the current context
+//! get more weight.
+//!
+//! [`cm`] is the high-ratio alternative: a bitwise context-mixing model over
+//! orders 0 to 4 and a match model, driving a binary arithmetic coder.
 
-/// Stub structure for future β-Context models.
-#[derive(Default)]
-pub struct BetaContext; 
\ No newline at end of file
+pub mod cm;
+
+/// Bits of probability precision in a [`Distribution`].
+pub const PROB_BITS: u32 = 16;
+/// Total of every [`Distribution`]'s frequencies.
+pub const PROB_SCALE: u32 = 1 << PROB_BITS;
+
+/// Highest context order [`BetaContext`] supports.
+pub const MAX_ORDER: usize = 2;
+
+/// Default number of order-2 hash bits: 4096 contexts.
+pub const DEFAULT_HASH_BITS: u32 = 12;
+
+// Per-symbol score every byte gets, so none is ever impossible.
+const FLOOR: u64 = 32;
+// Weight of each order's prediction relative to order 0.
+const WEIGHTS: [u64; MAX_ORDER + 1] = [1, 2, 4];
+// A context's counts are halved once their total reaches this, so the model
+// keeps adapting.
+const COUNT_LIMIT: u32 = 1 << 10;
+
+/// Probabilities of the 256 byte values as frequencies summing to
+/// [`PROB_SCALE`], each at least 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Distribution {
+    freq: [u32; 256],
+    cum: [u32; 257],
+}
+
+impl Default for Distribution {
+    fn default() -> Self {
+        Self::uniform()
+    }
+}
+
+impl Distribution {
+    /// Every byte equally likely.
+    pub fn uniform() -> Self {
+        let mut d = Self { freq: [PROB_SCALE / 256; 256], cum: [0; 257] };
+        d.accumulate();
+        d
+    }
+
+    /// Frequency of `sym`.
+    pub fn freq(&self, sym: u8) -> u32 {
+        self.freq[sym as usize]
+    }
+
+    /// Sum of the frequencies of the symbols below `sym`.
+    pub fn cum(&self, sym: u8) -> u32 {
+        self.cum[sym as usize]
+    }
+
+    /// All 256 frequencies.
+    pub fn freqs(&self) -> &[u32; 256] {
+        &self.freq
+    }
+
+    /// Symbol whose range `[cum, cum + freq)` contains `slot`.
+    ///
+    /// # Panics
+    /// If `slot >= PROB_SCALE`.
+    pub fn symbol_at(&self, slot: u32) -> u8 {
+        assert!(slot < PROB_SCALE, "slot {slot} out of range");
+        (self.cum.partition_point(|&c| c <= slot) - 1) as u8
+    }
+
+    fn accumulate(&mut self) {
+        for s in 0..256 {
+            self.cum[s + 1] = self.cum[s] + self.freq[s];
+        }
+        debug_assert_eq!(self.cum[256], PROB_SCALE);
+    }
+}
+
+/// A predictor an entropy coder can drive symbol by symbol.
+///
+/// Coding a byte is `predict`, code it with the returned distribution, then
+/// `update` with the byte. The decoder makes the same calls in the same order.
+pub trait ContextModel {
+    /// Distribution for the next byte. Does not change the model.
+    fn predict(&self) -> &Distribution;
+
+    /// Records `byte` as the next byte of the stream.
+    fn update(&mut self, byte: u8);
+}
+
+/// Settings for [`BetaContext::with_options`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextOptions {
+    pub(crate) max_order: usize,
+    pub(crate) hash_bits: u32,
+}
+
+impl Default for ContextOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ContextOptions {
+    /// Default settings: orders 0 to 2, [`DEFAULT_HASH_BITS`] order-2 bits.
+    pub fn new() -> Self {
+        Self { max_order: MAX_ORDER, hash_bits: DEFAULT_HASH_BITS }
+    }
+
+    /// Highest order to blend in.
+    ///
+    /// # Panics
+    /// If `order > MAX_ORDER`.
+    pub fn max_order(mut self, order: usize) -> Self {
+        assert!(order <= MAX_ORDER, "order {order} above {MAX_ORDER}");
+        self.max_order = order;
+        self
+    }
+
+    /// Number of order-2 contexts as a power of two. With 16 bits every pair
+    /// of previous bytes has its own table; fewer share tables by hash.
+    ///
+    /// # Panics
+    /// If `bits` is not in `1..=16`.
+    pub fn hash_bits(mut self, bits: u32) -> Self {
+        assert!((1..=16).contains(&bits), "hash bits {bits} not in 1..=16");
+        self.hash_bits = bits;
+        self
+    }
+}
+
+/// Adaptive counts for every context of one order.
+#[derive(Debug, Clone)]
+struct CountTable {
+    counts: Vec<u16>,
+    totals: Vec<u32>,
+}
+
+impl CountTable {
+    fn new(contexts: usize) -> Self {
+        Self { counts: vec![0; contexts * 256], totals: vec![0; contexts] }
+    }
+
+    fn context(&self, ctx: usize) -> (&[u16], u32) {
+        (&self.counts[ctx * 256..][..256], self.totals[ctx])
+    }
+
+    fn add(&mut self, ctx: usize, byte: u8) {
+        let counts = &mut self.counts[ctx * 256..][..256];
+        counts[byte as usize] += 1;
+        self.totals[ctx] += 1;
+        if self.totals[ctx] >= COUNT_LIMIT {
+            let mut total = 0;
+            for c in counts.iter_mut() {
+                *c -= *c >> 1;
+                total += *c as u32;
+            }
+            self.totals[ctx] = total;
+        }
+    }
+}
+
+/// Order-0/1/2 blended byte model.
+#[derive(Debug, Clone)]
+pub struct BetaContext {
+    hash_bits: u32,
+    tables: Vec<CountTable>,
+    // Previous two bytes, most recent in the low byte; zero before the start.
+    history: u16,
+    prediction: Distribution,
+}
+
+impl Default for BetaContext {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl BetaContext {
+    /// Creates a model with default settings.
+    pub fn new() -> Self {
+        Self::with_options(ContextOptions::new())
+    }
+
+    /// Creates a model with explicit settings.
+    pub fn with_options(options: ContextOptions) -> Self {
+        let sizes = [1, 256, 1 << options.hash_bits];
+        Self {
+            hash_bits: options.hash_bits,
+            tables: sizes[..=options.max_order].iter().map(|&n| CountTable::new(n)).collect(),
+            history: 0,
+            prediction: Distribution::uniform(),
+        }
+    }
+
+    /// Highest order in the blend.
+    pub fn max_order(&self) -> usize {
+        self.tables.len() - 1
+    }
+
+    /// Context index of `order` for the current history.
+    fn context(&self, order: usize) -> usize {
+        match order {
+            0 => 0,
+            1 => (self.history & 0xff) as usize,
+            _ if self.hash_bits == 16 => self.history as usize,
+            _ => (self.history as u32).wrapping_mul(0x9e37_79b1) as usize >> (32 - self.hash_bits),
+        }
+    }
+
+    /// Rebuilds the cached prediction from the current contexts.
+    fn mix(&mut self) {
+        let mut score = [FLOOR; 256];
+        for (order, table) in self.tables.iter().enumerate() {
+            let (counts, total) = table.context(self.context(order));
+            if total == 0 {
+                continue;
+            }
+            let factor = (WEIGHTS[order] << PROB_BITS) / (total as u64 + 1);
+            for (s, &c) in score.iter_mut().zip(counts) {
+                *s += c as u64 * factor;
+            }
+        }
+
+        let sum: u64 = score.iter().sum();
+        let freq = &mut self.prediction.freq;
+        for (f, &s) in freq.iter_mut().zip(&score) {
+            *f = ((s << PROB_BITS) / sum).max(1) as u32;
+        }
+        // Rounding leaves the total a little off; settle it on the most likely
+        // symbol, which has at least PROB_SCALE / 256 to give.
+        let top = (0..256).max_by_key(|&s| (freq[s], std::cmp::Reverse(s))).unwrap();
+        let total: u32 = freq.iter().sum();
+        freq[top] = freq[top] + PROB_SCALE - total;
+        self.prediction.accumulate();
+    }
+}
+
+impl ContextModel for BetaContext {
+    fn predict(&self) -> &Distribution {
+        &self.prediction
+    }
+
+    fn update(&mut self, byte: u8) {
+        for order in 0..self.tables.len() {
+            let ctx = self.context(order);
+            self.tables[order].add(ctx, byte);
+        }
+        self.history = self.history << 8 | byte as u16;
+        self.mix();
+    }
+}
@@ -0,0 +1,490 @@
+//! Bitwise context-mixing predictor in the style of lpaq.
+//!
+//! Bytes are coded MSB first, one binary decision at a time. For each bit,
+//! order-0 to order-4 context tables and a match model each predict the
+//! probability of a 1. A small neural mixer combines their predictions in the
+//! logistic domain (`stretch(p) = ln(p / (1 - p))`) with weights trained
+//! online, and an adaptive probability map (APM) refines the result.
+//!
+//! [`CmModel::p`] and [`CmModel::update`] play the roles of
+//! [`super::ContextModel::predict`] and [`super::ContextModel::update`] at bit
+//! level; encoder and decoder make the same calls and stay in lockstep. All
+//! arithmetic is integer. [`CmCoder`] pairs the model with
+//! [`crate::arith`] as an [`AlphaFlowEncode`] coder.
+
+use crate::alpha_flow::AlphaFlowEncode;
+use crate::arith;
+use crate::error::{CodecError, Result};
+use std::sync::OnceLock;
+
+/// Context orders with their own tables: 0 to 4.
+pub const ORDERS: usize = 5;
+/// Default log2 of the slots in each order's table.
+pub const DEFAULT_MEM_BITS: u32 = 20;
+/// Smallest accepted table size.
+pub const MIN_MEM_BITS: u32 = 10;
+/// Largest accepted table size: 16 MiB per order.
+pub const MAX_MEM_BITS: u32 = 22;
+
+/// Shortest context the match model looks up.
+const MIN_MATCH: usize = 6;
+/// Longest match length the model distinguishes.
+const MAX_MATCH_LEN: usize = 15;
+/// Inputs to the mixer: one per order, the match model and a bias.
+const INPUTS: usize = ORDERS + 2;
+/// Adaptation limit of the context tables' counts.
+const COUNT_LIMIT: u32 = 30;
+
+/// Version byte at the start of a [`CmCoder`] stream.
+pub const FORMAT_VERSION: u8 = 1;
+const HEADER_LEN: usize = 10;
+
+/// `stretch` and `squash` lookup tables, shared by all models.
+struct Curves {
+    stretch: Vec<i16>,
+}
+
+fn curves() -> &'static Curves {
+    static CURVES: OnceLock<Curves> = OnceLock::new();
+    CURVES.get_or_init(|| {
+        // Invert `squash` so that `squash(stretch(p)) ≈ p`.
+        let mut stretch = vec![0i16; 4096];
+        let mut pi = 0;
+        for x in -2047..=2047 {
+            let v = squash(x) as usize;
+            for s in &mut stretch[pi..=v] {
+                *s = x as i16;
+            }
+            pi = v + 1;
+        }
+        for s in &mut stretch[pi..] {
+            *s = 2047;
+        }
+        Curves { stretch }
+    })
+}
+
+/// Logistic function: maps `d` in `-2047..=2047` (8 fractional bits) to a
+/// 12-bit probability.
+fn squash(d: i32) -> i32 {
+    const T: [i32; 33] = [
+        1, 2, 3, 6, 10, 16, 27, 45, 73, 120, 194, 310, 488, 747, 1101, 1546, 2047, 2549, 2994, 3348, 3607, 3785, 3901,
+        3975, 4022, 4050, 4068, 4079, 4085, 4089, 4092, 4093, 4094,
+    ];
+    if d > 2047 {
+        return 4095;
+    }
+    if d < -2047 {
+        return 1;
+    }
+    let w = d & 127;
+    let i = ((d >> 7) + 16) as usize;
+    (T[i] * (128 - w) + T[i + 1] * w + 64) >> 7
+}
+
+/// Inverse of [`squash`].
+fn stretch(p: u32) -> i32 {
+    curves().stretch[p as usize] as i32
+}
+
+/// Adaptive probabilities with per-slot counts: the top 22 bits of a slot
+/// are P(1), the low 10 bits how often it has been updated.
+fn slot_p(slot: u32) -> u32 {
+    (slot >> 20).clamp(1, 4095)
+}
+
+fn slot_update(slot: &mut u32, bit: u8) {
+    // Step size 2 / (n + 1.5): fast at first, slower as evidence builds.
+    const fn reciprocal(n: u32) -> i32 {
+        (16384 / (n + n + 3)) as i32
+    }
+    const DT: [i32; 1024] = {
+        let mut dt = [0; 1024];
+        let mut n = 0;
+        while n < 1024 {
+            dt[n] = reciprocal(n as u32);
+            n += 1;
+        }
+        dt
+    };
+    let n = *slot & 1023;
+    let p = (*slot >> 10) as i32;
+    if n < COUNT_LIMIT {
+        *slot += 1;
+    }
+    let target = (bit as i32) << 22;
+    let delta = ((target - p) >> 3).wrapping_mul(DT[n as usize]) & !1023;
+    *slot = slot.wrapping_add(delta as u32);
+}
+
+const SLOT_INIT: u32 = 1 << 31;
+
+/// Adaptive probability map: refines a probability given a small context by
+/// interpolating between 33 trained points along the stretch axis.
+#[derive(Debug, Clone)]
+struct Apm {
+    t: Vec<u16>,
+    index: usize,
+}
+
+impl Apm {
+    const RATE: i32 = 7;
+
+    fn new(contexts: usize) -> Self {
+        let row: Vec<u16> = (0..33).map(|j| (squash((j - 16) * 128) * 16) as u16).collect();
+        Self { t: row.repeat(contexts), index: 0 }
+    }
+
+    fn refine(&mut self, p: u32, cx: usize) -> u32 {
+        let s = stretch(p) + 2048;
+        let lo = s & 127;
+        self.index = (s >> 7) as usize + cx * 33;
+        let (a, b) = (self.t[self.index] as i32, self.t[self.index + 1] as i32);
+        ((a * (128 - lo) + b * lo) >> 11) as u32
+    }
+
+    fn update(&mut self, bit: u8) {
+        let y = bit as i32;
+        let g = (y << 16) + (y << Self::RATE) - y - y;
+        for t in &mut self.t[self.index..=self.index + 1] {
+            *t = (*t as i32 + ((g - *t as i32) >> Self::RATE)) as u16;
+        }
+    }
+}
+
+/// Predicts the next bit from the longest earlier occurrence of the current
+/// context.
+#[derive(Debug, Clone)]
+struct MatchModel {
+    history: Vec<u8>,
+    // Last position following each hashed `MIN_MATCH`-byte context.
+    table: Vec<u32>,
+    bits: u32,
+    ptr: usize,
+    len: usize,
+    // Bit the match predicts, or `None` once this byte has diverged.
+    expected: Option<u8>,
+    slots: Vec<u32>,
+}
+
+impl MatchModel {
+    fn new(bits: u32) -> Self {
+        Self {
+            history: Vec::new(),
+            table: vec![0; 1 << bits],
+            bits,
+            ptr: 0,
+            len: 0,
+            expected: None,
+            slots: vec![SLOT_INIT; 2 * (MAX_MATCH_LEN + 1)],
+        }
+    }
+
+    fn slot(&self) -> Option<usize> {
+        self.expected.map(|bit| self.len.min(MAX_MATCH_LEN) * 2 + bit as usize)
+    }
+
+    fn input(&self) -> i32 {
+        self.slot().map_or(0, |s| stretch(slot_p(self.slots[s])))
+    }
+
+    fn update_bit(&mut self, bit: u8, bit_pos: u32, c0: u32) {
+        if let Some(s) = self.slot() {
+            slot_update(&mut self.slots[s], bit);
+            if self.expected != Some(bit) {
+                self.len = 0;
+            }
+        }
+        self.expected = None;
+        if self.len > 0 && bit_pos < 8 {
+            let predicted = self.history[self.ptr] as u32 | 0x100;
+            if predicted >> (8 - bit_pos) == c0 {
+                self.expected = Some((predicted >> (7 - bit_pos)) as u8 & 1);
+            }
+        }
+    }
+
+    fn update_byte(&mut self, byte: u8) {
+        self.history.push(byte);
+        let pos = self.history.len();
+        if self.len > 0 {
+            self.len += 1;
+            self.ptr += 1;
+        }
+        if pos >= MIN_MATCH {
+            let h = self.history[pos - MIN_MATCH..]
+                .iter()
+                .fold(0u32, |h, &b| (h ^ b as u32).wrapping_mul(0x0100_0193))
+                .wrapping_mul(0x9e37_79b1)
+                >> (32 - self.bits);
+            if self.len == 0 {
+                let candidate = self.table[h as usize] as usize;
+                if candidate > 0 {
+                    let len = (1..=candidate.min(MAX_MATCH_LEN))
+                        .take_while(|&i| self.history[candidate - i] == self.history[pos - i])
+                        .count();
+                    if len >= MIN_MATCH {
+                        (self.len, self.ptr) = (len, candidate);
+                    }
+                }
+            }
+            self.table[h as usize] = pos as u32;
+        }
+        self.expected = None;
+        if self.len > 0 {
+            self.expected = Some(self.history[self.ptr] >> 7);
+        }
+    }
+}
+
+/// Online-trained logistic mixer with one weight set per partial byte.
+#[derive(Debug, Clone)]
+struct Mixer {
+    weights: Vec<i32>,
+    inputs: [i32; INPUTS],
+    set: usize,
+    p: u32,
+}
+
+impl Mixer {
+    const LEARNING_RATE: i32 = 6;
+
+    fn new() -> Self {
+        Self { weights: vec![1 << 14; 256 * INPUTS], inputs: [0; INPUTS], set: 0, p: 2048 }
+    }
+
+    fn mix(&mut self, set: usize) -> u32 {
+        self.set = set * INPUTS;
+        let w = &self.weights[self.set..][..INPUTS];
+        let dot: i64 = self.inputs.iter().zip(w).map(|(&x, &w)| x as i64 * w as i64).sum();
+        self.p = squash((dot >> 16).clamp(-2047, 2047) as i32) as u32;
+        self.p
+    }
+
+    fn update(&mut self, bit: u8) {
+        let err = (((bit as i32) << 12) - self.p as i32) * Self::LEARNING_RATE;
+        for (w, &x) in self.weights[self.set..][..INPUTS].iter_mut().zip(&self.inputs) {
+            *w += (x * err + 0x8000) >> 16;
+        }
+    }
+}
+
+/// Bitwise context-mixing model.
+#[derive(Debug, Clone)]
+pub struct CmModel {
+    mem_bits: u32,
+    tables: Vec<Vec<u32>>,
+    // Context hash of each order for the current byte.
+    hashes: [u32; ORDERS],
+    // Slot each order used for the current bit.
+    slots: [usize; ORDERS],
+    matcher: MatchModel,
+    mixer: Mixer,
+    apm: Apm,
+    // Partial byte with a leading 1 bit: 1, 1b, 1bb, ...
+    c0: u32,
+    bit_pos: u32,
+    // Last four bytes, most recent lowest.
+    c4: u32,
+    p_mix: u32,
+    p: u32,
+}
+
+impl Default for CmModel {
+    fn default() -> Self {
+        Self::new(DEFAULT_MEM_BITS)
+    }
+}
+
+impl CmModel {
+    /// Creates a model with `1 << mem_bits` slots per order.
+    ///
+    /// # Panics
+    /// If `mem_bits` is outside `MIN_MEM_BITS..=MAX_MEM_BITS`.
+    pub fn new(mem_bits: u32) -> Self {
+        assert!((MIN_MEM_BITS..=MAX_MEM_BITS).contains(&mem_bits), "mem bits {mem_bits} out of range");
+        let mut model = Self {
+            mem_bits,
+            tables: (0..ORDERS).map(|_| vec![SLOT_INIT; 1 << mem_bits]).collect(),
+            hashes: [0; ORDERS],
+            slots: [0; ORDERS],
+            matcher: MatchModel::new(mem_bits - 2),
+            mixer: Mixer::new(),
+            apm: Apm::new(256),
+            c0: 1,
+            bit_pos: 0,
+            c4: 0,
+            p_mix: 2048,
+            p: 2048,
+        };
+        model.predict();
+        model
+    }
+
+    /// Probability that the next bit is 1, in `1..4096`.
+    pub fn p(&self) -> u32 {
+        self.p
+    }
+
+    /// Records the next bit and prepares the prediction for the one after.
+    pub fn update(&mut self, bit: u8) {
+        debug_assert!(bit <= 1);
+        for (table, &slot) in self.tables.iter_mut().zip(&self.slots) {
+            slot_update(&mut table[slot], bit);
+        }
+        self.mixer.update(bit);
+        self.apm.update(bit);
+
+        self.c0 = self.c0 << 1 | bit as u32;
+        self.bit_pos += 1;
+        self.matcher.update_bit(bit, self.bit_pos, self.c0);
+        if self.bit_pos == 8 {
+            let byte = self.c0 as u8;
+            self.c4 = self.c4 << 8 | byte as u32;
+            self.matcher.update_byte(byte);
+            for (order, h) in self.hashes.iter_mut().enumerate() {
+                let ctx = if order == 0 { 0 } else { self.c4 & (u32::MAX >> (32 - 8 * order)) };
+                *h = (ctx.wrapping_add(order as u32) ^ (order as u32) << 28).wrapping_mul(0x2c7d_b9e5);
+            }
+            self.c0 = 1;
+            self.bit_pos = 0;
+        }
+        self.predict();
+    }
+
+    /// Codes the eight bits of `byte` with `coder`, updating the model.
+    pub fn encode_byte(&mut self, coder: &mut arith::Encoder, byte: u8) {
+        for i in (0..8).rev() {
+            let bit = (byte >> i) & 1;
+            coder.encode(bit, self.p);
+            self.update(bit);
+        }
+    }
+
+    /// Decodes one byte from `coder`, updating the model.
+    pub fn decode_byte(&mut self, coder: &mut arith::Decoder<'_>) -> u8 {
+        for _ in 0..8 {
+            let bit = coder.decode(self.p);
+            self.update(bit);
+        }
+        *self.matcher.history.last().unwrap()
+    }
+
+    fn predict(&mut self) {
+        let shift = 32 - self.mem_bits;
+        for order in 0..ORDERS {
+            let h = self.hashes[order].wrapping_add(self.c0.wrapping_mul(0x9e37_79b1)).wrapping_mul(0x85eb_ca6b);
+            self.slots[order] = (h >> shift) as usize;
+            self.mixer.inputs[order] = stretch(slot_p(self.tables[order][self.slots[order]]));
+        }
+        self.mixer.inputs[ORDERS] = self.matcher.input();
+        self.mixer.inputs[ORDERS + 1] = 256;
+        self.p_mix = self.mixer.mix(self.c0 as usize);
+        let refined = self.apm.refine(self.p_mix, self.c0 as usize);
+        self.p = ((self.p_mix + 3 * refined + 2) >> 2).clamp(1, 4095);
+    }
+}
+
+/// High-ratio [`AlphaFlowEncode`] coder: [`CmModel`] driving [`crate::arith`].
+///
+/// ```text
+/// version:  u8 = FORMAT_VERSION
+/// mem_bits: u8        table size the encoder used
+/// len:      u64 LE    decoded length
+/// stream:   arithmetic-coded bits, MSB first per byte
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CmCoder {
+    mem_bits: u32,
+    max_output: u64,
+}
+
+impl Default for CmCoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl CmCoder {
+    /// Default settings: [`DEFAULT_MEM_BITS`] tables, no output limit.
+    pub fn new() -> Self {
+        Self { mem_bits: DEFAULT_MEM_BITS, max_output: u64::MAX }
+    }
+
+    /// Largest table size worth using for `len` bytes: one slot per coded
+    /// bit, so a short stream cannot make the decoder allocate big tables.
+    fn mem_bits_for(len: u64) -> u32 {
+        let bits = u64::BITS - len.saturating_mul(8).saturating_sub(1).leading_zeros();
+        bits.clamp(MIN_MEM_BITS, MAX_MEM_BITS)
+    }
+
+    /// Table size for encoding; more bits means fewer hash collisions and
+    /// `5 << (mem_bits + 2)` bytes of tables on both sides. Short inputs use
+    /// smaller tables, as the decoder rejects more than the length needs.
+    ///
+    /// # Panics
+    /// If `bits` is outside `MIN_MEM_BITS..=MAX_MEM_BITS`.
+    pub fn mem_bits(mut self, bits: u32) -> Self {
+        assert!((MIN_MEM_BITS..=MAX_MEM_BITS).contains(&bits), "mem bits {bits} out of range");
+        self.mem_bits = bits;
+        self
+    }
+
+    /// Fails with [`CodecError::LimitExceeded`] instead of decoding more than
+    /// `limit` bytes.
+    pub fn max_output(mut self, limit: u64) -> Self {
+        self.max_output = limit;
+        self
+    }
+}
+
+impl AlphaFlowEncode for CmCoder {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        let mem_bits = self.mem_bits.min(Self::mem_bits_for(input.len() as u64));
+        let mut model = CmModel::new(mem_bits);
+        let mut coder = arith::Encoder::new();
+        for &b in input {
+            model.encode_byte(&mut coder, b);
+        }
+        let mut out = vec![FORMAT_VERSION, mem_bits as u8];
+        out.extend_from_slice(&(input.len() as u64).to_le_bytes());
+        out.extend_from_slice(&coder.finish());
+        out
+    }
+
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        self.decode_capped(compressed, self.max_output)
+    }
+
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>> {
+        let (header, stream) = compressed.split_first_chunk::<HEADER_LEN>().ok_or(CodecError::Truncated)?;
+        if header[0] != FORMAT_VERSION {
+            return Err(CodecError::UnsupportedVersion(header[0]));
+        }
+        let mem_bits = header[1] as u32;
+        if !(MIN_MEM_BITS..=MAX_MEM_BITS).contains(&mem_bits) {
+            return Err(CodecError::Corrupt);
+        }
+        let len = u64::from_le_bytes(header[2..].try_into().unwrap());
+        if len > self.max_output.min(max_len) || usize::try_from(len).is_err() {
+            return Err(CodecError::LimitExceeded);
+        }
+        // The encoder never uses more than the length needs, so bigger tables
+        // are a forged header; refuse them before allocating.
+        if mem_bits > Self::mem_bits_for(len) {
+            return Err(CodecError::Corrupt);
+        }
+
+        let mut model = CmModel::new(mem_bits);
+        let mut coder = arith::Decoder::new(stream);
+        // The length is not backed by the input size, so grow as we go.
+        let mut out = Vec::with_capacity((len as usize).min(stream.len().saturating_mul(8)));
+        for _ in 0..len {
+            out.push(model.decode_byte(&mut coder));
+            if coder.overran() {
+                return Err(CodecError::Truncated);
+            }
+        }
+        Ok(out)
+    }
+}
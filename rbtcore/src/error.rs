@@ -0,0 +1,57 @@
+//! Error type shared by the rbtcore codecs.
+
+use crate::ledger::LedgerError;
+use std::fmt;
+
+/// Reasons a codec can fail to decode its input.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CodecError {
+    /// Input ended before the encoded data was complete.
+    Truncated,
+    /// Input is well-formed but inconsistent.
+    Corrupt,
+    /// Decoded data does not match its stored checksum.
+    Checksum,
+    /// Input declares a format version this build cannot read.
+    UnsupportedVersion(u8),
+    /// Decoding would exceed a configured size or count limit.
+    LimitExceeded,
+}
+
+/// Result type for rbtcore codec operations.
+pub type Result<T> = std::result::Result<T, CodecError>;
+
+impl fmt::Display for CodecError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CodecError::Truncated => f.write_str("encoded input is truncated"),
+            CodecError::Corrupt => f.write_str("encoded input is corrupt"),
+            CodecError::Checksum => f.write_str("checksum mismatch"),
+            CodecError::UnsupportedVersion(v) => write!(f, "unsupported format version {v}"),
+            CodecError::LimitExceeded => f.write_str("decoding exceeds a resource limit"),
+        }
+    }
+}
+
+impl std::error::Error for CodecError {}
+
+impl From<LedgerError> for CodecError {
+    fn from(e: LedgerError) -> Self {
+        match e {
+            LedgerError::TooSmall => CodecError::Truncated,
+            LedgerError::Malformed => CodecError::Corrupt,
+            LedgerError::UnsupportedVersion(v) => CodecError::UnsupportedVersion(v),
+            LedgerError::TooManyRuns | LedgerError::OutputTooLarge => CodecError::LimitExceeded,
+        }
+    }
+}
+
+impl From<CodecError> for std::io::Error {
+    fn from(e: CodecError) -> Self {
+        let kind = match e {
+            CodecError::Truncated => std::io::ErrorKind::UnexpectedEof,
+            _ => std::io::ErrorKind::InvalidData,
+        };
+        std::io::Error::new(kind, e)
+    }
+}
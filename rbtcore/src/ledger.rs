@@ -0,0 +1,317 @@
+//! Run-length ledger (L0), a port of `ledgerizer/src/ledgerizer.cpp`.
+//!
+//! Consecutive identical bytes ("even loops") become `(count, value)` runs.
+//! A ledger serializes in one of two formats, both shared with the C++ code.
+//! [`Format::V1`] is the original C++ `serialize` layout, 5 bytes per run:
+//!
+//! ```text
+//! num_runs: u32 LE
+//! per run:
+//!   count:  u32 LE
+//!   value:  u8
+//! ```
+//!
+//! [`Format::V2`] LEB128-encodes the counts and stores them apart from the
+//! values, so an entropy coder sees two streams with their own statistics:
+//!
+//! ```text
+//! magic:    "LDG" 0xFF    read as a V1 run count, over 4 billion runs
+//! version:  u8 = 2
+//! num_runs: LEB128
+//! counts:   num_runs × LEB128
+//! values:   num_runs × u8
+//! ```
+//!
+//! [`deserialize`] reads either; [`serialize_smallest`] picks whichever is
+//! shorter for a given ledger.
+//!
+//! Serialized ledgers may come from untrusted input, where a few bytes can
+//! declare billions of runs of billions of bytes. [`deserialize_with`] and
+//! [`decode_with`] take [`ReadOptions`] caps and check every declared size
+//! against them, and against the buffer, before allocating for it.
+
+use crate::varint;
+use std::fmt;
+
+/// One run: `count` copies of `value`.
+pub type Run = (u32, u8);
+
+/// A byte stream as a list of runs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ledger {
+    pub runs: Vec<Run>,
+}
+
+impl Ledger {
+    /// Number of bytes [`decode`] produces.
+    pub fn decoded_len(&self) -> u64 {
+        self.runs.iter().map(|&(count, _)| count as u64).sum()
+    }
+}
+
+/// Serialized ledger layouts.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// Fixed 5-byte runs, as written by the C++ `serialize`.
+    #[default]
+    V1,
+    /// Magic, version byte, then LEB128 counts and raw values in two streams.
+    V2,
+}
+
+/// Reasons [`deserialize`] fails, matching the C++ exceptions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LedgerError {
+    /// Fewer than 4 bytes, so no run count.
+    TooSmall,
+    /// The buffer ends inside a run, or a V2 field is malformed.
+    Malformed,
+    /// A V2 magic with a version this reader doesn't know.
+    UnsupportedVersion(u8),
+    /// The ledger declares more runs than [`ReadOptions::max_runs`].
+    TooManyRuns,
+    /// The runs add up to more than [`ReadOptions::max_output`] bytes.
+    OutputTooLarge,
+}
+
+impl fmt::Display for LedgerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LedgerError::TooSmall => write!(f, "buffer too small to contain ledger"),
+            LedgerError::Malformed => write!(f, "malformed ledger buffer"),
+            LedgerError::UnsupportedVersion(v) => write!(f, "unsupported ledger format version {v}"),
+            LedgerError::TooManyRuns => write!(f, "ledger run count exceeds the limit"),
+            LedgerError::OutputTooLarge => write!(f, "ledger output exceeds the size limit"),
+        }
+    }
+}
+
+impl std::error::Error for LedgerError {}
+
+/// Caps for [`deserialize_with`] and [`decode_with`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadOptions {
+    pub(crate) max_runs: usize,
+    pub(crate) max_output: u64,
+}
+
+impl Default for ReadOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ReadOptions {
+    /// No caps beyond what the buffer itself can hold.
+    pub fn new() -> Self {
+        Self { max_runs: usize::MAX, max_output: u64::MAX }
+    }
+
+    /// Fails with [`LedgerError::TooManyRuns`] on ledgers declaring more than
+    /// `limit` runs, before any run is read.
+    pub fn max_runs(mut self, limit: usize) -> Self {
+        self.max_runs = limit;
+        self
+    }
+
+    /// Fails with [`LedgerError::OutputTooLarge`] on ledgers that would decode
+    /// to more than `limit` bytes, as soon as the counts read so far pass it.
+    pub fn max_output(mut self, limit: u64) -> Self {
+        self.max_output = limit;
+        self
+    }
+}
+
+/// Bytes per V1 run.
+const RUN_LEN: usize = 5;
+/// First four bytes of a V2 ledger.
+pub const MAGIC_V2: [u8; 4] = *b"LDG\xff";
+const VERSION_2: u8 = 2;
+
+/// Groups `data` into runs. A run longer than `u32::MAX` is split.
+pub fn encode(data: &[u8]) -> Ledger {
+    let mut runs = Vec::new();
+    let Some((&first, rest)) = data.split_first() else {
+        return Ledger { runs };
+    };
+    let (mut count, mut current) = (1u32, first);
+    for &b in rest {
+        if b == current && count < u32::MAX {
+            count += 1;
+        } else {
+            runs.push((count, current));
+            (count, current) = (1, b);
+        }
+    }
+    runs.push((count, current));
+    Ledger { runs }
+}
+
+/// Expands `ledger` back into bytes.
+///
+/// Allocates [`Ledger::decoded_len`] bytes unchecked; use [`decode_with`]
+/// for ledgers read from untrusted input.
+pub fn decode(ledger: &Ledger) -> Vec<u8> {
+    let mut data = Vec::new();
+    for &(count, value) in &ledger.runs {
+        data.resize(data.len() + count as usize, value);
+    }
+    data
+}
+
+/// Writes `ledger` in the V1 layout, which every C++ reader accepts.
+///
+/// # Panics
+/// If there are more than `u32::MAX` runs.
+pub fn serialize(ledger: &Ledger) -> Vec<u8> {
+    serialize_as(ledger, Format::V1)
+}
+
+/// Writes `ledger` in `format`.
+///
+/// # Panics
+/// If there are more than `u32::MAX` runs.
+pub fn serialize_as(ledger: &Ledger, format: Format) -> Vec<u8> {
+    let num_runs = u32::try_from(ledger.runs.len()).expect("too many runs for a u32 count");
+    let mut buf = Vec::with_capacity(serialized_len(ledger, format));
+    match format {
+        Format::V1 => {
+            buf.extend_from_slice(&num_runs.to_le_bytes());
+            for &(count, value) in &ledger.runs {
+                buf.extend_from_slice(&count.to_le_bytes());
+                buf.push(value);
+            }
+        }
+        Format::V2 => {
+            buf.extend_from_slice(&MAGIC_V2);
+            buf.push(VERSION_2);
+            varint::write(&mut buf, num_runs);
+            for &(count, _) in &ledger.runs {
+                varint::write(&mut buf, count);
+            }
+            buf.extend(ledger.runs.iter().map(|&(_, value)| value));
+        }
+    }
+    buf
+}
+
+/// Writes `ledger` in whichever format is shorter, V1 on a tie.
+///
+/// # Panics
+/// If there are more than `u32::MAX` runs.
+pub fn serialize_smallest(ledger: &Ledger) -> Vec<u8> {
+    serialize_as(ledger, smallest_format(ledger))
+}
+
+/// The format [`serialize_smallest`] would use.
+pub fn smallest_format(ledger: &Ledger) -> Format {
+    if serialized_len(ledger, Format::V2) < serialized_len(ledger, Format::V1) { Format::V2 } else { Format::V1 }
+}
+
+/// Length of `ledger` serialized in `format`, without writing it.
+pub fn serialized_len(ledger: &Ledger, format: Format) -> usize {
+    let n = ledger.runs.len();
+    match format {
+        Format::V1 => 4 + n * RUN_LEN,
+        Format::V2 => {
+            let counts: usize = ledger.runs.iter().map(|&(count, _)| varint::len(count)).sum();
+            MAGIC_V2.len() + 1 + varint::len(n as u32) + counts + n
+        }
+    }
+}
+
+/// Format of a serialized ledger, judged by its first bytes. Buffers too
+/// short to hold a V2 magic are V1.
+pub fn format_of(buf: &[u8]) -> Format {
+    if buf.starts_with(&MAGIC_V2) { Format::V2 } else { Format::V1 }
+}
+
+/// Reads a ledger in either format, without caps.
+///
+/// V1 buffers are read like the C++ reader does, ignoring bytes after the
+/// last run. V2 buffers must end exactly after the values.
+pub fn deserialize(buf: &[u8]) -> Result<Ledger, LedgerError> {
+    deserialize_with(buf, &ReadOptions::new())
+}
+
+/// [`deserialize`] with caps on the run count and decoded size.
+///
+/// A run count that exceeds `max_runs` or cannot fit in the buffer fails
+/// before any run is read, so memory use stays within the buffer size.
+pub fn deserialize_with(buf: &[u8], options: &ReadOptions) -> Result<Ledger, LedgerError> {
+    match format_of(buf) {
+        Format::V1 => deserialize_v1(buf, options),
+        Format::V2 => deserialize_v2(&buf[MAGIC_V2.len()..], options),
+    }
+}
+
+/// [`decode`] that first checks `ledger` against `options`.
+pub fn decode_with(ledger: &Ledger, options: &ReadOptions) -> Result<Vec<u8>, LedgerError> {
+    if ledger.runs.len() > options.max_runs {
+        return Err(LedgerError::TooManyRuns);
+    }
+    let len = ledger.decoded_len();
+    if len > options.max_output || usize::try_from(len).is_err() {
+        return Err(LedgerError::OutputTooLarge);
+    }
+    Ok(decode(ledger))
+}
+
+/// Checks a declared run count: against the cap, then against `available`
+/// bytes at `min_len` bytes per run.
+fn check_runs(num_runs: u32, available: usize, min_len: usize, options: &ReadOptions) -> Result<usize, LedgerError> {
+    let num_runs = num_runs as usize;
+    if num_runs > options.max_runs {
+        return Err(LedgerError::TooManyRuns);
+    }
+    if num_runs > available / min_len {
+        return Err(LedgerError::Malformed);
+    }
+    Ok(num_runs)
+}
+
+/// Running decoded size, failing once it passes the cap.
+struct OutputBudget {
+    total: u64,
+    limit: u64,
+}
+
+impl OutputBudget {
+    fn add(&mut self, count: u32) -> Result<(), LedgerError> {
+        self.total += count as u64;
+        if self.total > self.limit { Err(LedgerError::OutputTooLarge) } else { Ok(()) }
+    }
+}
+
+fn deserialize_v1(buf: &[u8], options: &ReadOptions) -> Result<Ledger, LedgerError> {
+    let (head, rest) = buf.split_first_chunk::<4>().ok_or(LedgerError::TooSmall)?;
+    let num_runs = check_runs(u32::from_le_bytes(*head), rest.len(), RUN_LEN, options)?;
+    let mut budget = OutputBudget { total: 0, limit: options.max_output };
+    let mut runs = Vec::with_capacity(num_runs);
+    for run in rest.chunks_exact(RUN_LEN).take(num_runs) {
+        let count = u32::from_le_bytes([run[0], run[1], run[2], run[3]]);
+        budget.add(count)?;
+        runs.push((count, run[4]));
+    }
+    Ok(Ledger { runs })
+}
+
+fn deserialize_v2(buf: &[u8], options: &ReadOptions) -> Result<Ledger, LedgerError> {
+    let (&version, mut rest) = buf.split_first().ok_or(LedgerError::TooSmall)?;
+    if version != VERSION_2 {
+        return Err(LedgerError::UnsupportedVersion(version));
+    }
+    // Each run needs at least a count byte and a value byte.
+    let num_runs = check_runs(varint::read(&mut rest).ok_or(LedgerError::Malformed)?, rest.len(), 2, options)?;
+    let mut budget = OutputBudget { total: 0, limit: options.max_output };
+    let mut counts = Vec::with_capacity(num_runs);
+    for _ in 0..num_runs {
+        let count = varint::read(&mut rest).ok_or(LedgerError::Malformed)?;
+        budget.add(count)?;
+        counts.push(count);
+    }
+    if rest.len() != num_runs {
+        return Err(LedgerError::Malformed);
+    }
+    Ok(Ledger { runs: counts.into_iter().zip(rest.iter().copied()).collect() })
+}
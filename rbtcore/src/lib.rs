@@ -1,8 +1,16 @@
 //! Core codec algorithms (α-Flow, β-Context, γ-Controller)
 
 pub mod alpha_flow;
+pub mod arith;
 pub mod beta_context;
+pub mod error;
 pub mod gamma_control;
+pub mod ledger;
+pub mod lz77;
+pub mod stream;
+mod varint;
+
+pub use error::{CodecError, Result};
 
 /// Returns rbtcore crate version.
 pub fn version() -> &'static str {
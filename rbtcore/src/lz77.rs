@@ -0,0 +1,437 @@
+//! LZ77 parse stage: repeated strings as back-references.
+//!
+//! [`parse`] turns bytes into [`Token`]s, each a literal byte or a match that
+//! copies `len` bytes from `distance` bytes back; [`expand`] reverses it. The
+//! match finder hashes the next [`MIN_MATCH`] bytes into a table of chain
+//! heads and links every position to the previous one with the same hash,
+//! walking at most [`LzOptions::max_chain`] candidates within the window.
+//! With lazy matching it also tries the next position and emits a literal
+//! instead when that finds a longer match.
+//!
+//! [`TokenStreams`] splits tokens into four byte streams with their own
+//! statistics, for an entropy coder to take on separately:
+//!
+//! ```text
+//! literals:    the literal bytes
+//! lit_runs:    per match, LEB128 literals before it
+//! match_lens:  per match, LEB128 len - MIN_MATCH
+//! distances:   per match, LEB128 distance - 1
+//! ```
+//!
+//! Literals after the last match are implied by what is left of `literals`.
+//! [`LzCoder`] writes the four streams as an [`AlphaFlowEncode`] coder.
+
+use crate::alpha_flow::AlphaFlowEncode;
+use crate::error::{CodecError, Result};
+use crate::varint;
+
+/// Shortest match the parser emits.
+pub const MIN_MATCH: u32 = 4;
+/// Longest match the parser emits and decoders accept.
+pub const MAX_MATCH: u32 = 1 << 16;
+/// Default window: matches reach back up to 1 MiB.
+pub const DEFAULT_WINDOW: usize = 1 << 20;
+/// Largest window: 64 MiB.
+pub const MAX_WINDOW: usize = 64 << 20;
+/// Default number of chain candidates tried per position.
+pub const DEFAULT_MAX_CHAIN: u32 = 64;
+/// Default match length that ends the search early.
+pub const DEFAULT_NICE_LEN: u32 = 128;
+
+/// Version byte at the start of an [`LzCoder`] stream.
+pub const FORMAT_VERSION: u8 = 1;
+
+/// Largest hash table: 2^18 chain heads.
+const MAX_HASH_BITS: u32 = 18;
+/// Chain link meaning "no earlier position".
+const NONE: u32 = u32::MAX;
+
+/// One step of a parse.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Token {
+    /// A byte copied to the output as is.
+    Literal(u8),
+    /// `len` bytes copied from `distance` bytes before the end of the output.
+    /// The source may overlap the bytes being written.
+    Match { len: u32, distance: u32 },
+}
+
+/// Settings for [`parse`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzOptions {
+    pub(crate) window: usize,
+    pub(crate) max_chain: u32,
+    pub(crate) nice_len: u32,
+    pub(crate) lazy: bool,
+}
+
+impl Default for LzOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LzOptions {
+    /// Default settings: [`DEFAULT_WINDOW`], [`DEFAULT_MAX_CHAIN`],
+    /// [`DEFAULT_NICE_LEN`], lazy matching on.
+    pub fn new() -> Self {
+        Self { window: DEFAULT_WINDOW, max_chain: DEFAULT_MAX_CHAIN, nice_len: DEFAULT_NICE_LEN, lazy: true }
+    }
+
+    /// Largest match distance. The chain table takes 4 bytes per window
+    /// byte, up to the input length.
+    ///
+    /// # Panics
+    /// If `bytes` is not in `1..=MAX_WINDOW`.
+    pub fn window(mut self, bytes: usize) -> Self {
+        assert!((1..=MAX_WINDOW).contains(&bytes), "window {bytes} not in 1..={MAX_WINDOW}");
+        self.window = bytes;
+        self
+    }
+
+    /// Candidates tried per position; more finds longer matches, slower.
+    ///
+    /// # Panics
+    /// If `limit` is 0.
+    pub fn max_chain(mut self, limit: u32) -> Self {
+        assert!(limit > 0, "max chain must be at least 1");
+        self.max_chain = limit;
+        self
+    }
+
+    /// Match length at which the search stops looking for a longer one.
+    ///
+    /// # Panics
+    /// If `len` is not in `MIN_MATCH..=MAX_MATCH`.
+    pub fn nice_len(mut self, len: u32) -> Self {
+        assert!((MIN_MATCH..=MAX_MATCH).contains(&len), "nice length {len} not in {MIN_MATCH}..={MAX_MATCH}");
+        self.nice_len = len;
+        self
+    }
+
+    /// Whether to defer a match by one byte when the next position has a
+    /// longer one.
+    pub fn lazy(mut self, lazy: bool) -> Self {
+        self.lazy = lazy;
+        self
+    }
+}
+
+/// Hash-chain match finder over one input.
+struct MatchFinder<'a> {
+    data: &'a [u8],
+    options: LzOptions,
+    hash_bits: u32,
+    head: Vec<u32>,
+    // Previous position with the same hash, indexed by position & mask. It
+    // covers the window, so a link is only overwritten once out of range.
+    prev: Vec<u32>,
+    mask: usize,
+}
+
+impl<'a> MatchFinder<'a> {
+    fn new(data: &'a [u8], options: LzOptions) -> Self {
+        let hash_bits = data.len().next_power_of_two().trailing_zeros().clamp(8, MAX_HASH_BITS);
+        let chain_len = options.window.min(data.len()).max(1).next_power_of_two();
+        Self {
+            data,
+            options,
+            hash_bits,
+            head: vec![NONE; 1 << hash_bits],
+            prev: vec![NONE; chain_len],
+            mask: chain_len - 1,
+        }
+    }
+
+    fn hash(&self, pos: usize) -> usize {
+        let v = u32::from_le_bytes(self.data[pos..pos + 4].try_into().unwrap());
+        (v.wrapping_mul(0x9e37_79b1) >> (32 - self.hash_bits)) as usize
+    }
+
+    /// Longest match for `pos` among earlier positions, then links `pos`
+    /// into its chain. Returns `(len, distance)`, `len` 0 if none reaches
+    /// [`MIN_MATCH`].
+    fn find_and_insert(&mut self, pos: usize) -> (u32, u32) {
+        if pos + MIN_MATCH as usize > self.data.len() {
+            return (0, 0);
+        }
+        let h = self.hash(pos);
+        let found = self.find(pos, self.head[h]);
+        self.prev[pos & self.mask] = self.head[h];
+        self.head[h] = pos as u32;
+        found
+    }
+
+    /// Links `pos` into its chain without searching.
+    fn insert(&mut self, pos: usize) {
+        if pos + MIN_MATCH as usize <= self.data.len() {
+            let h = self.hash(pos);
+            self.prev[pos & self.mask] = self.head[h];
+            self.head[h] = pos as u32;
+        }
+    }
+
+    fn find(&self, pos: usize, mut cand: u32) -> (u32, u32) {
+        let data = self.data;
+        let max_len = (data.len() - pos).min(MAX_MATCH as usize);
+        let (mut best_len, mut best_dist) = (0, 0);
+        for _ in 0..self.options.max_chain {
+            if cand == NONE || pos - cand as usize > self.options.window {
+                break;
+            }
+            let c = cand as usize;
+            // Only a candidate that also agrees at best_len can beat the best.
+            if best_len < max_len && data[c + best_len] == data[pos + best_len] {
+                let len = data[c..].iter().zip(&data[pos..pos + max_len]).take_while(|(a, b)| a == b).count();
+                if len > best_len {
+                    (best_len, best_dist) = (len, pos - c);
+                    if len >= self.options.nice_len as usize || len == max_len {
+                        break;
+                    }
+                }
+            }
+            let next = self.prev[c & self.mask];
+            if next == NONE || next >= cand {
+                break;
+            }
+            cand = next;
+        }
+        if best_len < MIN_MATCH as usize { (0, 0) } else { (best_len as u32, best_dist as u32) }
+    }
+}
+
+/// Parses `data` into literals and matches.
+///
+/// # Panics
+/// If `data` is 4 GiB or longer.
+pub fn parse(data: &[u8], options: &LzOptions) -> Vec<Token> {
+    assert!(data.len() < NONE as usize, "input too long for a u32 position");
+    let mut finder = MatchFinder::new(data, *options);
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    let mut current = finder.find_and_insert(0);
+    while pos < data.len() {
+        let (mut len, mut distance) = current;
+        if len == 0 {
+            tokens.push(Token::Literal(data[pos]));
+            pos += 1;
+            current = finder.find_and_insert(pos);
+            continue;
+        }
+        // Positions up to `inserted` are already linked into the chains.
+        let mut inserted = pos;
+        if options.lazy {
+            while len < options.nice_len && pos + 1 < data.len() {
+                let next = finder.find_and_insert(pos + 1);
+                inserted = pos + 1;
+                if next.0 <= len {
+                    break;
+                }
+                tokens.push(Token::Literal(data[pos]));
+                pos += 1;
+                (len, distance) = next;
+            }
+        }
+        for p in inserted + 1..pos + len as usize {
+            finder.insert(p);
+        }
+        tokens.push(Token::Match { len, distance });
+        pos += len as usize;
+        current = finder.find_and_insert(pos);
+    }
+    tokens
+}
+
+/// Expands `tokens` back into bytes.
+///
+/// Fails with [`CodecError::Corrupt`] on a match reaching before the start
+/// of the output or outside `MIN_MATCH..=MAX_MATCH`.
+pub fn expand(tokens: &[Token]) -> Result<Vec<u8>> {
+    expand_capped(tokens, u64::MAX)
+}
+
+/// [`expand`] that fails with [`CodecError::LimitExceeded`] once the output
+/// would pass `limit` bytes.
+fn expand_capped(tokens: &[Token], limit: u64) -> Result<Vec<u8>> {
+    let mut out: Vec<u8> = Vec::new();
+    for &token in tokens {
+        match token {
+            Token::Literal(b) => {
+                if out.len() as u64 >= limit {
+                    return Err(CodecError::LimitExceeded);
+                }
+                out.push(b);
+            }
+            Token::Match { len, distance } => {
+                let (len, distance) = (len as usize, distance as usize);
+                if !(MIN_MATCH as usize..=MAX_MATCH as usize).contains(&len) || distance == 0 || distance > out.len() {
+                    return Err(CodecError::Corrupt);
+                }
+                if (out.len() + len) as u64 > limit {
+                    return Err(CodecError::LimitExceeded);
+                }
+                let start = out.len() - distance;
+                if distance >= len {
+                    out.extend_from_within(start..start + len);
+                } else {
+                    // The source overlaps the copy: repeat the last `distance`
+                    // bytes, doubling the chunk as the output grows.
+                    let mut copied = 0;
+                    while copied < len {
+                        let n = (len - copied).min(distance + copied);
+                        out.extend_from_within(start..start + n);
+                        copied += n;
+                    }
+                }
+            }
+        }
+    }
+    Ok(out)
+}
+
+/// Tokens as separate literal and match streams; see the module docs.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenStreams {
+    pub literals: Vec<u8>,
+    pub lit_runs: Vec<u8>,
+    pub match_lens: Vec<u8>,
+    pub distances: Vec<u8>,
+}
+
+impl TokenStreams {
+    /// Splits `tokens` into streams.
+    ///
+    /// # Panics
+    /// If a match is shorter than [`MIN_MATCH`], has distance 0, or a literal
+    /// run is longer than `u32::MAX`.
+    pub fn split(tokens: &[Token]) -> Self {
+        let mut s = Self::default();
+        let mut run = 0u32;
+        for &token in tokens {
+            match token {
+                Token::Literal(b) => {
+                    s.literals.push(b);
+                    run = run.checked_add(1).expect("literal run too long for a u32");
+                }
+                Token::Match { len, distance } => {
+                    assert!(len >= MIN_MATCH && distance > 0, "invalid match {len}@{distance}");
+                    varint::write(&mut s.lit_runs, run);
+                    varint::write(&mut s.match_lens, len - MIN_MATCH);
+                    varint::write(&mut s.distances, distance - 1);
+                    run = 0;
+                }
+            }
+        }
+        s
+    }
+
+    /// Reassembles the tokens. Fails with [`CodecError::Corrupt`] if the
+    /// streams disagree on the number of matches, hold malformed LEB128, a
+    /// literal run longer than what is left of `literals`, or a match
+    /// longer than [`MAX_MATCH`]. Distances are checked by [`expand`].
+    pub fn join(&self) -> Result<Vec<Token>> {
+        join_slices([&self.lit_runs, &self.match_lens, &self.distances, &self.literals])
+    }
+}
+
+/// [`TokenStreams::join`] over borrowed streams, in the order `lit_runs`,
+/// `match_lens`, `distances`, `literals`.
+fn join_slices([mut lit_runs, mut match_lens, mut distances, literals]: [&[u8]; 4]) -> Result<Vec<Token>> {
+    let mut literals = literals.iter().copied();
+    let mut tokens = Vec::new();
+    while !lit_runs.is_empty() {
+        let run = varint::read(&mut lit_runs).ok_or(CodecError::Corrupt)? as usize;
+        let len = varint::read(&mut match_lens).ok_or(CodecError::Corrupt)?;
+        let distance = varint::read(&mut distances).ok_or(CodecError::Corrupt)?;
+        if run > literals.len() || len > MAX_MATCH - MIN_MATCH || distance == u32::MAX {
+            return Err(CodecError::Corrupt);
+        }
+        tokens.extend(literals.by_ref().take(run).map(Token::Literal));
+        tokens.push(Token::Match { len: len + MIN_MATCH, distance: distance + 1 });
+    }
+    if !match_lens.is_empty() || !distances.is_empty() {
+        return Err(CodecError::Corrupt);
+    }
+    tokens.extend(literals.map(Token::Literal));
+    Ok(tokens)
+}
+
+/// LZ77 as an [`AlphaFlowEncode`] coder: [`parse`] written as
+/// [`TokenStreams`], without entropy coding.
+///
+/// ```text
+/// version:        u8 = 1
+/// stream lengths: lit_runs, match_lens, distances, literals, LEB128 each
+/// streams:        the four streams in that order, ending the input
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LzCoder {
+    options: LzOptions,
+    max_output: u64,
+}
+
+impl Default for LzCoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl LzCoder {
+    /// Default parse settings, no output limit.
+    pub fn new() -> Self {
+        Self::with_options(LzOptions::new())
+    }
+
+    /// Creates a coder that parses with `options`.
+    pub fn with_options(options: LzOptions) -> Self {
+        Self { options, max_output: u64::MAX }
+    }
+
+    /// Fails with [`CodecError::LimitExceeded`] instead of decoding more than
+    /// `limit` bytes.
+    pub fn max_output(mut self, limit: u64) -> Self {
+        self.max_output = limit;
+        self
+    }
+}
+
+impl AlphaFlowEncode for LzCoder {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        let s = TokenStreams::split(&parse(input, &self.options));
+        let sections = [&s.lit_runs, &s.match_lens, &s.distances, &s.literals];
+        let mut out = vec![FORMAT_VERSION];
+        for section in sections {
+            varint::write(&mut out, section.len() as u32);
+        }
+        for section in sections {
+            out.extend_from_slice(section);
+        }
+        out
+    }
+
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>> {
+        self.decode_capped(compressed, u64::MAX)
+    }
+
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>> {
+        let (&version, mut rest) = compressed.split_first().ok_or(CodecError::Truncated)?;
+        if version != FORMAT_VERSION {
+            return Err(CodecError::UnsupportedVersion(version));
+        }
+        let mut lens = [0usize; 4];
+        for len in &mut lens {
+            *len = varint::read(&mut rest).ok_or(CodecError::Truncated)? as usize;
+        }
+        let mut sections = [&[][..]; 4];
+        for (section, len) in sections.iter_mut().zip(lens) {
+            if rest.len() < len {
+                return Err(CodecError::Truncated);
+            }
+            (*section, rest) = rest.split_at(len);
+        }
+        if !rest.is_empty() {
+            return Err(CodecError::Corrupt);
+        }
+        expand_capped(&join_slices(sections)?, self.max_output.min(max_len))
+    }
+}
@@ -0,0 +1,308 @@
+//! Streaming adapters that give any [`AlphaFlowEncode`] coder `io::Write` and
+//! `io::Read` front ends.
+//!
+//! [`Encoder`] cuts its input into blocks of [`StreamOptions::block_size`]
+//! bytes, encodes each one separately and writes it as a frame; [`Decoder`]
+//! reads and decodes one frame at a time. Memory use is a couple of blocks
+//! whatever the stream length. The framing is:
+//!
+//! ```text
+//! per block:
+//!   raw_len: u32 LE      decoded length, 1..=block_size
+//!   enc_len: u32 LE      length of the coder's output, at most
+//!                        8 × raw_len + 1024
+//!   payload: enc_len bytes
+//! end:
+//!   raw_len: u32 LE = 0
+//! ```
+//!
+//! The end frame lets the decoder tell a complete stream from one cut at a
+//! frame boundary. Bytes after it are left unread in the inner reader.
+
+use crate::alpha_flow::AlphaFlowEncode;
+use crate::error::CodecError;
+use std::io::{self, Read, Write};
+
+/// Block size used by [`StreamOptions::new`]: 1 MiB.
+pub const DEFAULT_BLOCK_SIZE: usize = 1 << 20;
+
+const FRAME_HEADER_LEN: usize = 8;
+
+/// Largest payload a frame of `raw_len` bytes may carry. Leaves room for
+/// coders that expand incompressible blocks, while keeping the payload
+/// buffer proportional to the block rather than to a declared `enc_len`.
+fn max_payload_len(raw_len: u64) -> u64 {
+    raw_len.saturating_mul(8).saturating_add(1024)
+}
+
+/// Settings for [`Encoder`] and [`Decoder`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StreamOptions {
+    pub(crate) block_size: usize,
+    pub(crate) max_output: u64,
+}
+
+impl Default for StreamOptions {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl StreamOptions {
+    /// Default settings: [`DEFAULT_BLOCK_SIZE`] blocks, no output limit.
+    pub fn new() -> Self {
+        Self { block_size: DEFAULT_BLOCK_SIZE, max_output: u64::MAX }
+    }
+
+    /// Bytes per block when encoding, and the largest block accepted when
+    /// decoding; larger frames fail with [`CodecError::LimitExceeded`].
+    ///
+    /// # Panics
+    /// If `size` is 0 or does not fit in a `u32`.
+    pub fn block_size(mut self, size: usize) -> Self {
+        assert!(size > 0 && u32::try_from(size).is_ok(), "block size must be in 1..=u32::MAX");
+        self.block_size = size;
+        self
+    }
+
+    /// Fails with [`CodecError::LimitExceeded`] once a decoded stream would
+    /// pass `limit` bytes. Checked against each frame header before decoding,
+    /// and the coder may not produce more than the header declares.
+    pub fn max_output(mut self, limit: u64) -> Self {
+        self.max_output = limit;
+        self
+    }
+}
+
+/// Streaming encoder writing framed blocks of `C`'s output to `W`.
+///
+/// Call [`Encoder::finish`] to write the final partial block and the end
+/// frame; dropping the encoder leaves a stream that decodes as truncated.
+#[derive(Debug)]
+pub struct Encoder<C: AlphaFlowEncode, W: Write> {
+    coder: C,
+    inner: W,
+    block_size: usize,
+    block: Vec<u8>,
+}
+
+impl<C: AlphaFlowEncode, W: Write> Encoder<C, W> {
+    /// Creates an encoder with default settings.
+    pub fn new(coder: C, inner: W) -> Self {
+        Self::with_options(coder, inner, StreamOptions::new())
+    }
+
+    /// Creates an encoder with explicit settings.
+    pub fn with_options(coder: C, inner: W, options: StreamOptions) -> Self {
+        Self { coder, inner, block_size: options.block_size, block: Vec::with_capacity(options.block_size) }
+    }
+
+    /// Underlying writer.
+    pub fn get_ref(&self) -> &W {
+        &self.inner
+    }
+
+    /// Mutable access to the underlying writer.
+    pub fn get_mut(&mut self) -> &mut W {
+        &mut self.inner
+    }
+
+    /// Writes the buffered partial block and the end frame, and returns the
+    /// underlying writer.
+    pub fn finish(mut self) -> io::Result<W> {
+        self.emit()?;
+        self.inner.write_all(&0u32.to_le_bytes())?;
+        Ok(self.inner)
+    }
+
+    /// Writes the buffered block, if any, as one frame.
+    fn emit(&mut self) -> io::Result<()> {
+        if self.block.is_empty() {
+            return Ok(());
+        }
+        let payload = self.coder.encode(&self.block);
+        let enc_len = u32::try_from(payload.len())
+            .ok()
+            .filter(|&n| n as u64 <= max_payload_len(self.block.len() as u64))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "encoded block too large for a frame"))?;
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        header[..4].copy_from_slice(&(self.block.len() as u32).to_le_bytes());
+        header[4..].copy_from_slice(&enc_len.to_le_bytes());
+        self.inner.write_all(&header)?;
+        self.inner.write_all(&payload)?;
+        self.block.clear();
+        Ok(())
+    }
+}
+
+impl<C: AlphaFlowEncode, W: Write> Write for Encoder<C, W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let take = (self.block_size - self.block.len()).min(buf.len());
+        self.block.extend_from_slice(&buf[..take]);
+        if self.block.len() == self.block_size {
+            self.emit()?;
+        }
+        Ok(take)
+    }
+
+    /// Ends the current block early so everything written so far can be
+    /// decoded, then flushes the underlying writer.
+    fn flush(&mut self) -> io::Result<()> {
+        self.emit()?;
+        self.inner.flush()
+    }
+}
+
+/// Streaming decoder reading framed blocks from `R` and decoding them with `C`.
+///
+/// Errors surface as [`io::Error`]s wrapping [`CodecError`] (`UnexpectedEof`
+/// for truncation, `InvalidData` otherwise), and every later read repeats
+/// the first error.
+#[derive(Debug)]
+pub struct Decoder<C: AlphaFlowEncode, R: Read> {
+    coder: C,
+    inner: R,
+    options: StreamOptions,
+    payload: Vec<u8>,
+    block: Vec<u8>,
+    pos: usize,
+    produced: u64,
+    done: bool,
+    error: Option<CodecError>,
+}
+
+impl<C: AlphaFlowEncode, R: Read> Decoder<C, R> {
+    /// Creates a decoder with default settings.
+    pub fn new(coder: C, inner: R) -> Self {
+        Self::with_options(coder, inner, StreamOptions::new())
+    }
+
+    /// Creates a decoder with explicit settings. The output limit applies to
+    /// the whole stream.
+    pub fn with_options(coder: C, inner: R, options: StreamOptions) -> Self {
+        Self {
+            coder,
+            inner,
+            options,
+            payload: Vec::new(),
+            block: Vec::new(),
+            pos: 0,
+            produced: 0,
+            done: false,
+            error: None,
+        }
+    }
+
+    /// Underlying reader.
+    pub fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    /// Returns the underlying reader, positioned after the end frame once
+    /// the stream has been read to the end.
+    pub fn into_inner(self) -> R {
+        self.inner
+    }
+
+    /// Reads and decodes the next frame. Returns `false` at the end frame.
+    fn next_frame(&mut self) -> io::Result<bool> {
+        let mut header = [0u8; FRAME_HEADER_LEN];
+        if read_full(&mut self.inner, &mut header[..4])? < 4 {
+            return Err(self.fail(CodecError::Truncated));
+        }
+        let raw_len = u32::from_le_bytes([header[0], header[1], header[2], header[3]]) as u64;
+        if raw_len == 0 {
+            self.done = true;
+            return Ok(false);
+        }
+        if raw_len > self.options.block_size as u64 || self.produced + raw_len > self.options.max_output {
+            return Err(self.fail(CodecError::LimitExceeded));
+        }
+        if read_full(&mut self.inner, &mut header[4..])? < 4 {
+            return Err(self.fail(CodecError::Truncated));
+        }
+        let enc_len = u32::from_le_bytes([header[4], header[5], header[6], header[7]]) as u64;
+        if enc_len > max_payload_len(raw_len) {
+            return Err(self.fail(CodecError::Corrupt));
+        }
+        // Grows with the bytes actually read, not with the declared length.
+        self.payload.clear();
+        if (&mut self.inner).take(enc_len).read_to_end(&mut self.payload)? as u64 != enc_len {
+            return Err(self.fail(CodecError::Truncated));
+        }
+        // The header's limits have passed, so a payload decoding to more
+        // than `raw_len` bytes is corrupt rather than over a limit.
+        match self.coder.decode_capped(&self.payload, raw_len) {
+            Ok(block) if block.len() as u64 == raw_len => self.block = block,
+            Ok(_) | Err(CodecError::LimitExceeded) => return Err(self.fail(CodecError::Corrupt)),
+            Err(e) => return Err(self.fail(e)),
+        }
+        self.pos = 0;
+        self.produced += raw_len;
+        Ok(true)
+    }
+
+    fn fail(&mut self, e: CodecError) -> io::Error {
+        self.error = Some(e);
+        e.into()
+    }
+}
+
+impl<C: AlphaFlowEncode, R: Read> Read for Decoder<C, R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if let Some(e) = self.error {
+            return Err(e.into());
+        }
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        while self.pos == self.block.len() {
+            if self.done || !self.next_frame()? {
+                return Ok(0);
+            }
+        }
+        let n = (self.block.len() - self.pos).min(buf.len());
+        buf[..n].copy_from_slice(&self.block[self.pos..self.pos + n]);
+        self.pos += n;
+        Ok(n)
+    }
+}
+
+/// Encodes all of `reader` into `writer` with `coder`; returns the number of
+/// input bytes.
+pub fn encode_stream<C: AlphaFlowEncode>(
+    coder: C,
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    options: StreamOptions,
+) -> io::Result<u64> {
+    let mut encoder = Encoder::with_options(coder, writer, options);
+    let n = io::copy(reader, &mut encoder)?;
+    encoder.finish()?;
+    Ok(n)
+}
+
+/// Decodes a stream written by [`Encoder`] from `reader` into `writer`;
+/// returns the number of output bytes.
+pub fn decode_stream<C: AlphaFlowEncode>(
+    coder: C,
+    reader: &mut impl Read,
+    writer: &mut impl Write,
+    options: StreamOptions,
+) -> io::Result<u64> {
+    io::copy(&mut Decoder::with_options(coder, reader, options), writer)
+}
+
+/// Fills `buf` from `r` unless it ends first; returns the bytes read.
+fn read_full(r: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut got = 0;
+    while got < buf.len() {
+        match r.read(&mut buf[got..]) {
+            Ok(0) => break,
+            Ok(n) => got += n,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(got)
+}
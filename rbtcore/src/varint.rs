@@ -0,0 +1,37 @@
+//! LEB128 variable-length `u32`s, shared by the serialized formats.
+
+/// Encoded length of `v` in bytes, 1 to 5.
+pub(crate) fn len(v: u32) -> usize {
+    (32 - v.leading_zeros() as usize).max(1).div_ceil(7)
+}
+
+/// Appends `v` to `buf`.
+pub(crate) fn write(buf: &mut Vec<u8>, mut v: u32) {
+    while v >= 0x80 {
+        buf.push(v as u8 | 0x80);
+        v >>= 7;
+    }
+    buf.push(v as u8);
+}
+
+/// Reads a value from the front of `buf` and advances past it. Returns
+/// `None` if `buf` ends first, the value exceeds `u32::MAX` or the encoding
+/// is padded, so every value has exactly one encoding.
+pub(crate) fn read(buf: &mut &[u8]) -> Option<u32> {
+    let mut v = 0u32;
+    for (i, &b) in buf.iter().enumerate().take(5) {
+        let bits = (b & 0x7f) as u32;
+        if i == 4 && bits > 0x0f {
+            return None;
+        }
+        v |= bits << (7 * i);
+        if b & 0x80 == 0 {
+            if b == 0 && i > 0 {
+                return None;
+            }
+            *buf = &buf[i + 1..];
+            return Some(v);
+        }
+    }
+    None
+}
@@ -1,10 +1,41 @@
+// The baseline test builds the unit coder through `Default`.
+#![allow(clippy::default_constructed_unit_structs)]
+
 use rbtcore::alpha_flow::{AlphaFlowEncode, NoopCoder};
+use rbtcore::CodecError;
+use rbtcore::ledger::{self, ReadOptions};
 
 #[test]
 fn roundtrip_noop() {
     let data = b"RBT codec test bytes";
     let coder = NoopCoder::default();
     let encoded = coder.encode(data);
-    let decoded = coder.decode(&encoded);
+    let decoded = coder.decode(&encoded).unwrap();
     assert_eq!(decoded, data);
-} 
\ No newline at end of file
+} 
+#[test]
+fn decode_errors_are_typed() {
+    // A coder over ledgers, which reports malformed input instead of panicking.
+    struct LedgerCoder;
+    impl AlphaFlowEncode for LedgerCoder {
+        fn encode(&self, input: &[u8]) -> Vec<u8> {
+            ledger::serialize_smallest(&ledger::encode(input))
+        }
+        fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>, CodecError> {
+            let options = ReadOptions::new().max_output(1 << 20);
+            Ok(ledger::decode_with(&ledger::deserialize_with(compressed, &options)?, &options)?)
+        }
+    }
+
+    let coder = LedgerCoder;
+    let encoded = coder.encode(b"aaaabbbbcccc");
+    assert_eq!(coder.decode(&encoded).unwrap(), b"aaaabbbbcccc");
+    assert_eq!(coder.decode(&encoded[..2]), Err(CodecError::Truncated));
+    assert_eq!(coder.decode(&encoded[..encoded.len() - 1]), Err(CodecError::Corrupt));
+    let mut version = encoded.clone();
+    version[4] = 9;
+    assert_eq!(coder.decode(&version), Err(CodecError::UnsupportedVersion(9)));
+    let bomb = [1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 0];
+    assert_eq!(coder.decode(&bomb), Err(CodecError::LimitExceeded));
+    assert!(!CodecError::Checksum.to_string().is_empty());
+}
@@ -0,0 +1,116 @@
+use ansx::rans::{RansDecoder, RansEncoder};
+use rbtcore::beta_context::{BetaContext, ContextModel, ContextOptions, Distribution, PROB_SCALE};
+
+fn sample_text(len: usize) -> Vec<u8> {
+    let words = ["ledger ", "codec ", "entropy ", "context ", "model ", "the ", "of ", "parity ", "block "];
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        out.extend_from_slice(words[(seed >> 40) as usize % words.len()].as_bytes());
+    }
+    out.truncate(len);
+    out
+}
+
+fn ideal_bits(model: &mut impl ContextModel, data: &[u8]) -> f64 {
+    data.iter()
+        .map(|&b| {
+            let bits = (PROB_SCALE as f64 / model.predict().freq(b) as f64).log2();
+            model.update(b);
+            bits
+        })
+        .sum()
+}
+
+fn encode(model: &mut impl ContextModel, data: &[u8]) -> Vec<u8> {
+    // rANS codes in reverse, so collect the ranges going forwards first.
+    let ranges: Vec<(u32, u32)> = data
+        .iter()
+        .map(|&b| {
+            let d = model.predict();
+            let range = (d.cum(b), d.freq(b));
+            model.update(b);
+            range
+        })
+        .collect();
+    let mut enc = RansEncoder::new();
+    for &(start, freq) in ranges.iter().rev() {
+        enc.put(start, freq);
+    }
+    enc.finish()
+}
+
+fn decode(model: &mut impl ContextModel, stream: &[u8], len: usize) -> Vec<u8> {
+    let mut dec = RansDecoder::new(stream).unwrap();
+    let mut out = Vec::with_capacity(len);
+    for _ in 0..len {
+        let d = model.predict();
+        let b = d.symbol_at(dec.peek());
+        dec.advance(d.cum(b), d.freq(b)).unwrap();
+        model.update(b);
+        out.push(b);
+    }
+    assert!(dec.is_finished());
+    out
+}
+
+#[test]
+fn distributions_are_complete() {
+    let mut model = BetaContext::new();
+    assert_eq!(*model.predict(), Distribution::uniform());
+    for &b in sample_text(5000).iter().chain(&[0, 255, 0, 255]) {
+        let d = model.predict();
+        assert_eq!(d.freqs().iter().sum::<u32>(), PROB_SCALE);
+        assert!(d.freqs().iter().all(|&f| f >= 1));
+        assert_eq!(d.symbol_at(d.cum(b)), b);
+        assert_eq!(d.symbol_at(d.cum(b) + d.freq(b) - 1), b);
+        model.update(b);
+    }
+}
+
+#[test]
+fn encoder_and_decoder_stay_in_lockstep() {
+    let data = sample_text(8_000);
+    for options in [
+        ContextOptions::new(),
+        ContextOptions::new().max_order(0),
+        ContextOptions::new().max_order(1),
+        ContextOptions::new().hash_bits(4),
+        ContextOptions::new().hash_bits(16),
+    ] {
+        let stream = encode(&mut BetaContext::with_options(options), &data);
+        assert_eq!(decode(&mut BetaContext::with_options(options), &stream, data.len()), data, "{options:?}");
+    }
+    let binary: Vec<u8> = (0..5_000u32).map(|i| (i.wrapping_mul(2_654_435_761) >> 24) as u8).collect();
+    let stream = encode(&mut BetaContext::new(), &binary);
+    assert_eq!(decode(&mut BetaContext::new(), &stream, binary.len()), binary);
+}
+
+#[test]
+fn higher_orders_predict_text_better() {
+    let data = sample_text(20_000);
+    let bits: Vec<f64> = (0..=2)
+        .map(|order| ideal_bits(&mut BetaContext::with_options(ContextOptions::new().max_order(order)), &data))
+        .collect();
+    assert!(bits[1] < bits[0] * 0.6, "{bits:?}");
+    assert!(bits[2] < bits[1] * 0.6, "{bits:?}");
+    let coded = encode(&mut BetaContext::new(), &data).len() as f64 * 8.0;
+    assert!((coded - bits[2]).abs() < 64.0 + bits[2] * 0.001, "{coded} vs {}", bits[2]);
+}
+
+#[test]
+fn adapts_to_a_change_in_statistics() {
+    let mut model = BetaContext::with_options(ContextOptions::new().max_order(0));
+    for _ in 0..5000 {
+        model.update(b'a');
+    }
+    assert!(model.predict().freq(b'a') > PROB_SCALE / 2);
+    for _ in 0..5000 {
+        model.update(b'b');
+    }
+    assert!(model.predict().freq(b'b') > PROB_SCALE / 2);
+    assert!(model.predict().freq(b'a') < PROB_SCALE / 16);
+}
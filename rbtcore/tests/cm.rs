@@ -0,0 +1,165 @@
+use rbtcore::CodecError;
+use rbtcore::alpha_flow::{AlphaFlowEncode, LedgerCoder, Mode, ModeCoder};
+use rbtcore::ledger::ReadOptions;
+use rbtcore::arith;
+use rbtcore::beta_context::cm::{CmCoder, CmModel, FORMAT_VERSION};
+use rbtcore::stream::{StreamOptions, decode_stream, encode_stream};
+
+fn sample_text(len: usize) -> Vec<u8> {
+    let words = ["ledger ", "codec ", "entropy ", "context ", "model ", "the ", "of ", "parity ", "block\n"];
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        out.extend_from_slice(words[(seed >> 40) as usize % words.len()].as_bytes());
+    }
+    out.truncate(len);
+    out
+}
+
+/// Little-endian records with a counter, a flag byte and noise.
+fn sample_binary(records: u32) -> Vec<u8> {
+    let mut seed = 7u32;
+    (0..records)
+        .flat_map(|i| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            let mut r = i.to_le_bytes().to_vec();
+            r.push(if i % 3 == 0 { 0x80 } else { 0 });
+            r.push((seed >> 24) as u8);
+            r
+        })
+        .collect()
+}
+
+#[test]
+fn arithmetic_coder_roundtrip() {
+    let bits: Vec<u8> = sample_text(2000).iter().flat_map(|&b| (0..8).map(move |i| (b >> i) & 1)).collect();
+    for p in [1, 100, 2048, 3000, 4095] {
+        let mut enc = arith::Encoder::new();
+        for &b in &bits {
+            enc.encode(b, p);
+        }
+        let stream = enc.finish();
+        let mut dec = arith::Decoder::new(&stream);
+        let decoded: Vec<u8> = bits.iter().map(|_| dec.decode(p)).collect();
+        assert_eq!(decoded, bits, "p {p}");
+        assert!(!dec.overran());
+    }
+}
+
+#[test]
+fn roundtrip() {
+    let text = sample_text(10_000);
+    let binary = sample_binary(5000);
+    for coder in [CmCoder::new().mem_bits(10), CmCoder::new().mem_bits(16)] {
+        for data in [&text[..], &binary, b"", b"x", &[0; 5000]] {
+            let encoded = coder.encode(data);
+            assert_eq!(coder.decode(&encoded).unwrap(), data, "{coder:?}, {} bytes", data.len());
+        }
+    }
+    let encoded = CmCoder::new().encode(&text);
+    assert_eq!(CmCoder::new().decode(&encoded).unwrap(), text);
+    // The table size travels in the header.
+    let encoded = CmCoder::new().mem_bits(12).encode(&text);
+    assert_eq!(CmCoder::new().decode(&encoded).unwrap(), text);
+}
+
+#[test]
+fn model_is_deterministic() {
+    let data = sample_binary(500);
+    let mut a = CmModel::new(12);
+    let mut b = CmModel::new(12);
+    for bit in data.iter().flat_map(|&byte| (0..8).rev().map(move |i| (byte >> i) & 1)) {
+        assert_eq!(a.p(), b.p());
+        assert!((1..4096).contains(&a.p()));
+        a.update(bit);
+        b.update(bit);
+    }
+}
+
+#[test]
+fn beats_the_ledger_path() {
+    // One byte in six of the binary records is noise.
+    for (data, ratio) in [(sample_text(30_000), 4), (sample_binary(5000), 2)] {
+        let cm = CmCoder::new().mem_bits(16).encode(&data).len();
+        let ledger = LedgerCoder::new().encode(&data).len();
+        assert!(cm * ratio < data.len(), "{cm} of {}", data.len());
+        assert!(cm * ratio < ledger, "cm {cm}, ledger {ledger}");
+    }
+}
+
+#[test]
+fn rejects_bad_streams() {
+    let coder = CmCoder::new().mem_bits(12);
+    let encoded = coder.encode(&sample_text(1000));
+    for len in 0..encoded.len() {
+        assert_eq!(coder.decode(&encoded[..len]), Err(CodecError::Truncated), "len {len}");
+    }
+
+    let mut version = encoded.clone();
+    version[0] = FORMAT_VERSION + 1;
+    assert_eq!(coder.decode(&version), Err(CodecError::UnsupportedVersion(FORMAT_VERSION + 1)));
+    let mut mem = encoded.clone();
+    mem[1] = 30;
+    assert_eq!(coder.decode(&mem), Err(CodecError::Corrupt));
+    // Tables bigger than the declared length needs are never written.
+    mem[1] = 22;
+    assert_eq!(coder.decode(&mem), Err(CodecError::Corrupt));
+    assert_eq!(CmCoder::new().encode(b"x")[1], 10);
+    assert_eq!(coder.max_output(999).decode(&encoded), Err(CodecError::LimitExceeded));
+    assert_eq!(coder.max_output(1000).decode(&encoded).unwrap().len(), 1000);
+
+    // A huge declared length backed by a tiny stream fails on the stream.
+    let mut bomb = encoded[..10].to_vec();
+    bomb[2..].copy_from_slice(&u32::MAX.to_le_bytes().repeat(2));
+    assert_eq!(coder.decode(&bomb), Err(CodecError::Truncated));
+}
+
+#[test]
+fn modes_are_selectable_and_self_describing() {
+    let data = sample_text(10_000);
+    let ledger = Mode::Ledger.encode(&data);
+    let high = Mode::HighRatio.encode(&data);
+    assert_eq!((ledger[0], high[0]), (0, 1));
+    assert!(high.len() * 4 < ledger.len());
+    for encoded in [&ledger, &high] {
+        assert_eq!(Mode::Ledger.decode(encoded).unwrap(), data);
+        assert_eq!(Mode::HighRatio.decode(encoded).unwrap(), data);
+    }
+    assert_eq!(Mode::Ledger.decode(&[]), Err(CodecError::Truncated));
+    assert_eq!(Mode::Ledger.decode(&[2, 0, 0, 0, 0]), Err(CodecError::UnsupportedVersion(2)));
+
+    let options = StreamOptions::new().block_size(4096);
+    let mut framed = Vec::new();
+    encode_stream(Mode::HighRatio, &mut &data[..], &mut framed, options).unwrap();
+    let mut out = Vec::new();
+    decode_stream(Mode::default(), &mut &framed[..], &mut out, options).unwrap();
+    assert_eq!(out, data);
+}
+
+#[test]
+fn capped_modes_reject_oversized_payloads() {
+    // A ledger payload of one run of u32::MAX bytes, and a CM header
+    // declaring as much.
+    let ledger_bomb = [0, 1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, b'x'];
+    let mut cm_bomb = vec![1, FORMAT_VERSION, 20];
+    cm_bomb.extend_from_slice(&(u32::MAX as u64).to_le_bytes());
+
+    let capped = ModeCoder::new(Mode::Ledger).max_output(1 << 20);
+    assert_eq!(capped.decode(&ledger_bomb), Err(CodecError::LimitExceeded));
+    assert_eq!(capped.decode(&cm_bomb), Err(CodecError::LimitExceeded));
+    let runs = ModeCoder::default().ledger_options(ReadOptions::new().max_runs(0));
+    assert_eq!(runs.decode(&ledger_bomb), Err(CodecError::LimitExceeded));
+    assert_eq!(Mode::Ledger.decode_capped(&ledger_bomb, 1 << 20), Err(CodecError::LimitExceeded));
+
+    let data = sample_text(5_000);
+    for mode in [Mode::Ledger, Mode::HighRatio] {
+        let encoded = mode.encode(&data);
+        assert_eq!(ModeCoder::new(mode).encode(&data), encoded);
+        assert_eq!(capped.decode(&encoded).unwrap(), data);
+        let tight = ModeCoder::new(mode).max_output(data.len() as u64 - 1);
+        assert_eq!(tight.decode(&encoded), Err(CodecError::LimitExceeded));
+    }
+}
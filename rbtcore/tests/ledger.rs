@@ -0,0 +1,146 @@
+use rbtcore::ledger::{self, Format, Ledger, LedgerError, ReadOptions};
+use std::fs;
+use std::path::Path;
+
+const FIXTURES: [&str; 7] = ["empty", "basic", "single", "text", "long_run", "all_values", "runs_random"];
+
+fn fixture(name: &str, ext: &str) -> Vec<u8> {
+    let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/ledger").join(format!("{name}.{ext}"));
+    fs::read(&path).unwrap_or_else(|e| panic!("{}: {e}", path.display()))
+}
+
+#[test]
+fn matches_cpp_fixtures() {
+    for name in FIXTURES {
+        let raw = fixture(name, "raw");
+        let led = fixture(name, "led");
+        let ledger = ledger::encode(&raw);
+        assert_eq!(ledger::serialize(&ledger), led, "{name}");
+        assert_eq!(ledger::deserialize(&led).unwrap(), ledger, "{name}");
+        assert_eq!(ledger::decode(&ledger), raw, "{name}");
+
+        let led2 = fixture(name, "led2");
+        assert_eq!(ledger::serialize_as(&ledger, Format::V2), led2, "{name}");
+        assert_eq!(ledger::format_of(&led2), Format::V2);
+        assert_eq!(ledger::deserialize(&led2).unwrap(), ledger, "{name}");
+
+        let smallest = ledger::serialize_smallest(&ledger);
+        assert_eq!(smallest.len(), led.len().min(led2.len()), "{name}");
+        assert_eq!(ledger::deserialize(&smallest).unwrap(), ledger, "{name}");
+    }
+}
+
+#[test]
+fn picks_the_smaller_format() {
+    let short_runs = ledger::encode(b"abcabcabc");
+    assert_eq!(ledger::smallest_format(&short_runs), Format::V2);
+    assert_eq!(ledger::serialized_len(&short_runs, Format::V2), 4 + 1 + 1 + 9 + 9);
+    // An empty ledger is 4 bytes in V1 and 6 in V2.
+    assert_eq!(ledger::smallest_format(&Ledger::default()), Format::V1);
+    assert_eq!(ledger::serialize_smallest(&Ledger::default()), [0, 0, 0, 0]);
+    // Counts that need all 5 LEB128 bytes make V2 a byte longer per run.
+    let huge = Ledger { runs: vec![(u32::MAX, 1), (u32::MAX, 2)] };
+    assert_eq!(ledger::smallest_format(&huge), Format::V1);
+    for format in [Format::V1, Format::V2] {
+        let buf = ledger::serialize_as(&huge, format);
+        assert_eq!(buf.len(), ledger::serialized_len(&huge, format));
+        assert_eq!(ledger::deserialize(&buf).unwrap(), huge);
+    }
+}
+
+#[test]
+fn rejects_malformed_v2() {
+    let led2 = fixture("basic", "led2");
+    for len in 4..led2.len() {
+        assert!(ledger::deserialize(&led2[..len]).is_err(), "len {len}");
+    }
+    let mut trailing = led2.clone();
+    trailing.push(0);
+    assert_eq!(ledger::deserialize(&trailing), Err(LedgerError::Malformed));
+
+    let mut version = led2.clone();
+    version[4] = 3;
+    assert_eq!(ledger::deserialize(&version), Err(LedgerError::UnsupportedVersion(3)));
+
+    let header = [&ledger::MAGIC_V2[..], &[2]].concat();
+    // Padded (0x81 0x00) and oversized (> u32::MAX) run counts.
+    for count in [&[0x81, 0x00][..], &[0xff, 0xff, 0xff, 0xff, 0x1f], &[0x80; 6]] {
+        let buf = [&header[..], count].concat();
+        assert_eq!(ledger::deserialize(&buf), Err(LedgerError::Malformed), "{count:x?}");
+    }
+}
+
+#[test]
+fn basic_roundtrip() {
+    let data = [1, 1, 1, 2, 2, 3, 3, 3, 3, 4];
+    let ledger = ledger::encode(&data);
+    assert_eq!(ledger.runs, [(3, 1), (2, 2), (4, 3), (1, 4)]);
+    assert_eq!(ledger::decode(&ledger), data);
+    assert_eq!(ledger::encode(&[]), Ledger::default());
+    assert_eq!(ledger::serialize(&Ledger::default()), [0, 0, 0, 0]);
+}
+
+#[test]
+fn rejects_short_buffers() {
+    assert_eq!(ledger::deserialize(&[]), Err(LedgerError::TooSmall));
+    assert_eq!(ledger::deserialize(&[1, 0, 0]), Err(LedgerError::TooSmall));
+    let led = fixture("basic", "led");
+    for len in 4..led.len() {
+        assert_eq!(ledger::deserialize(&led[..len]), Err(LedgerError::Malformed), "len {len}");
+    }
+    // A huge run count with no runs fails without allocating for it.
+    assert_eq!(ledger::deserialize(&[0xff; 4]), Err(LedgerError::Malformed));
+
+    // Trailing bytes are ignored, as in C++.
+    let mut padded = led.clone();
+    padded.push(0xaa);
+    assert_eq!(ledger::deserialize(&padded), ledger::deserialize(&led));
+}
+
+#[test]
+fn caps_runs_and_output() {
+    let led = fixture("runs_random", "led");
+    let ledger = ledger::deserialize(&led).unwrap();
+    let runs = ledger.runs.len();
+    let len = ledger.decoded_len();
+    assert_eq!(len, fixture("runs_random", "raw").len() as u64);
+
+    for buf in [led, fixture("runs_random", "led2")] {
+        let exact = ReadOptions::new().max_runs(runs).max_output(len);
+        assert_eq!(ledger::deserialize_with(&buf, &exact).unwrap(), ledger);
+        let fewer_runs = ReadOptions::new().max_runs(runs - 1);
+        assert_eq!(ledger::deserialize_with(&buf, &fewer_runs), Err(LedgerError::TooManyRuns));
+        let less_output = ReadOptions::new().max_output(len - 1);
+        assert_eq!(ledger::deserialize_with(&buf, &less_output), Err(LedgerError::OutputTooLarge));
+    }
+
+    let options = ReadOptions::new().max_output(len - 1);
+    assert_eq!(ledger::decode_with(&ledger, &options), Err(LedgerError::OutputTooLarge));
+    assert_eq!(ledger::decode_with(&ledger, &ReadOptions::new().max_runs(runs - 1)), Err(LedgerError::TooManyRuns));
+    assert_eq!(ledger::decode_with(&ledger, &ReadOptions::new()).unwrap(), ledger::decode(&ledger));
+}
+
+#[test]
+fn hostile_headers_fail_before_allocating() {
+    let options = ReadOptions::new().max_output(1 << 20);
+
+    // 2³² - 1 runs declared, only one present.
+    let mut v1 = vec![0xff; 4];
+    v1.extend([0xff, 0xff, 0xff, 0xff, 7]);
+    assert_eq!(ledger::deserialize_with(&v1, &ReadOptions::new()), Err(LedgerError::Malformed));
+    // One run of 2³² - 1 bytes fails on the count, not in `decode`.
+    let v1 = [1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, 7];
+    assert_eq!(ledger::deserialize_with(&v1, &options), Err(LedgerError::OutputTooLarge));
+
+    let v2 = [&ledger::MAGIC_V2[..], &[2, 0xff, 0xff, 0xff, 0xff, 0x0f, 0x01, 7]].concat();
+    assert_eq!(ledger::deserialize_with(&v2, &ReadOptions::new()), Err(LedgerError::Malformed));
+    let v2 = [&ledger::MAGIC_V2[..], &[2, 1, 0xff, 0xff, 0xff, 0xff, 0x0f, 7]].concat();
+    assert_eq!(ledger::deserialize_with(&v2, &options), Err(LedgerError::OutputTooLarge));
+
+    // Many maximal runs: the total overflows a u32 but not the u64 budget.
+    let huge = Ledger { runs: vec![(u32::MAX, 0); 3] };
+    let buf = ledger::serialize_as(&huge, Format::V2);
+    assert_eq!(ledger::deserialize_with(&buf, &options), Err(LedgerError::OutputTooLarge));
+    assert_eq!(ledger::deserialize(&buf).unwrap().decoded_len(), 3 * u32::MAX as u64);
+    assert_eq!(ledger::decode_with(&huge, &options), Err(LedgerError::OutputTooLarge));
+}
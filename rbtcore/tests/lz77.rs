@@ -0,0 +1,190 @@
+use rbtcore::CodecError;
+use rbtcore::alpha_flow::AlphaFlowEncode;
+use rbtcore::lz77::{
+    FORMAT_VERSION, LzCoder, LzOptions, MAX_MATCH, MAX_WINDOW, MIN_MATCH, Token, TokenStreams, expand, parse,
+};
+
+fn sample_text(len: usize) -> Vec<u8> {
+    let words = ["ledger ", "codec ", "entropy ", "context ", "model ", "the ", "of ", "parity ", "block\n"];
+    let mut seed = 0x2545_f491_4f6c_dd1du64;
+    let mut out = Vec::with_capacity(len);
+    while out.len() < len {
+        seed ^= seed << 13;
+        seed ^= seed >> 7;
+        seed ^= seed << 17;
+        out.extend_from_slice(words[(seed >> 40) as usize % words.len()].as_bytes());
+    }
+    out.truncate(len);
+    out
+}
+
+fn noise(len: usize, mut seed: u32) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(1_664_525).wrapping_add(1_013_904_223);
+            (seed >> 24) as u8
+        })
+        .collect()
+}
+
+fn samples() -> Vec<Vec<u8>> {
+    let mut runs = vec![0u8; 200_000];
+    runs.extend((0..50_000u32).map(|i| (i / 16 % 8) as u8));
+    let mut repeated = noise(5_000, 3);
+    repeated.extend_from_within(..);
+    repeated.extend(noise(100, 4));
+    repeated.extend_from_within(1_000..4_000);
+    vec![
+        Vec::new(),
+        b"a".to_vec(),
+        b"abcabcabcabcabc".to_vec(),
+        sample_text(100_000),
+        runs,
+        noise(50_000, 1),
+        repeated,
+    ]
+}
+
+#[test]
+fn parse_roundtrips() {
+    let options = [LzOptions::new(), LzOptions::new().lazy(false), LzOptions::new().window(64).max_chain(1)];
+    for data in samples() {
+        for opts in &options {
+            let tokens = parse(&data, opts);
+            assert_eq!(expand(&tokens).unwrap(), data, "len {} with {opts:?}", data.len());
+        }
+    }
+}
+
+#[test]
+fn matches_respect_limits() {
+    for window in [1, 100, 4096] {
+        let opts = LzOptions::new().window(window);
+        for data in samples() {
+            for token in parse(&data, &opts) {
+                if let Token::Match { len, distance } = token {
+                    assert!((MIN_MATCH..=MAX_MATCH).contains(&len));
+                    assert!(1 <= distance && distance as usize <= window, "distance {distance} > {window}");
+                }
+            }
+        }
+    }
+}
+
+#[test]
+fn repeats_become_few_tokens() {
+    let mut data = noise(10_000, 9);
+    data.extend_from_within(..);
+    data.extend_from_within(..);
+    let tokens = parse(&data, &LzOptions::new());
+    assert_eq!(tokens.len(), 10_000 + 1, "{:?}", &tokens[10_000..]);
+    assert_eq!(tokens[10_000], Token::Match { len: 30_000, distance: 10_000 });
+
+    let zeros = vec![0u8; 1 << 20];
+    let tokens = parse(&zeros, &LzOptions::new());
+    assert!(tokens.len() <= 2 + (1 << 20) / MAX_MATCH as usize, "{} tokens", tokens.len());
+
+    // Text only repeats words, so distances reach back far and matches vary.
+    let text = sample_text(100_000);
+    let matched: u32 =
+        parse(&text, &LzOptions::new()).iter().map(|t| if let Token::Match { len, .. } = t { *len } else { 0 }).sum();
+    assert!(matched as usize > text.len() * 9 / 10, "only {matched} bytes matched");
+}
+
+#[test]
+fn max_window_reaches_past_default() {
+    // A block repeated 4 MiB later, past the default window.
+    let block = noise(4096, 5);
+    let mut data = block.clone();
+    data.resize(4 << 20, 0);
+    data.extend_from_slice(&block);
+
+    let tokens = parse(&data, &LzOptions::new().window(MAX_WINDOW));
+    assert_eq!(tokens.last(), Some(&Token::Match { len: 4096, distance: 4 << 20 }));
+    assert_eq!(expand(&tokens).unwrap(), data);
+
+    let short = parse(&data, &LzOptions::new());
+    assert!(!short.iter().any(|t| matches!(t, Token::Match { distance, .. } if *distance > 1 << 20)));
+}
+
+#[test]
+#[should_panic(expected = "window")]
+fn window_above_max_panics() {
+    let _ = LzOptions::new().window(MAX_WINDOW + 1);
+}
+
+#[test]
+fn streams_roundtrip() {
+    for data in samples() {
+        let tokens = parse(&data, &LzOptions::new());
+        let streams = TokenStreams::split(&tokens);
+        let literals = tokens.iter().filter(|t| matches!(t, Token::Literal(_))).count();
+        assert_eq!(streams.literals.len(), literals);
+        assert_eq!(streams.join().unwrap(), tokens);
+    }
+}
+
+#[test]
+fn corrupt_tokens_rejected() {
+    let lit = Token::Literal(7);
+    for tokens in [
+        vec![Token::Match { len: 4, distance: 1 }],
+        vec![lit, Token::Match { len: 4, distance: 2 }],
+        vec![lit, Token::Match { len: 4, distance: 0 }],
+        vec![lit, Token::Match { len: MIN_MATCH - 1, distance: 1 }],
+        vec![lit, Token::Match { len: MAX_MATCH + 1, distance: 1 }],
+    ] {
+        assert_eq!(expand(&tokens), Err(CodecError::Corrupt), "{tokens:?}");
+    }
+
+    let good = TokenStreams::split(&parse(b"abcdabcdabcd", &LzOptions::new()));
+    let mut cases = Vec::new();
+    let mut s = good.clone();
+    s.distances.clear();
+    cases.push(s);
+    let mut s = good.clone();
+    s.match_lens.push(0);
+    cases.push(s);
+    let mut s = good.clone();
+    s.lit_runs = vec![0x80];
+    cases.push(s);
+    let mut s = good.clone();
+    s.lit_runs = vec![100];
+    cases.push(s);
+    for s in cases {
+        assert_eq!(s.join(), Err(CodecError::Corrupt), "{s:?}");
+    }
+}
+
+#[test]
+fn coder_roundtrips_and_shrinks_repeats() {
+    let coder = LzCoder::new();
+    for data in samples() {
+        assert_eq!(coder.decode(&coder.encode(&data)).unwrap(), data);
+    }
+    let text = sample_text(100_000);
+    let encoded = coder.encode(&text);
+    assert!(encoded.len() * 3 < text.len(), "{} bytes", encoded.len());
+}
+
+#[test]
+fn coder_rejects_bad_input() {
+    let coder = LzCoder::new();
+    let data = sample_text(10_000);
+    let encoded = coder.encode(&data);
+    assert_eq!(encoded[0], FORMAT_VERSION);
+
+    assert_eq!(coder.decode(&[]), Err(CodecError::Truncated));
+    assert_eq!(coder.decode(&[9]), Err(CodecError::UnsupportedVersion(9)));
+    for cut in [1, 3, encoded.len() / 2, encoded.len() - 1] {
+        assert_eq!(coder.decode(&encoded[..cut]), Err(CodecError::Truncated), "cut at {cut}");
+    }
+    let mut long = encoded.clone();
+    long.push(0);
+    assert_eq!(coder.decode(&long), Err(CodecError::Corrupt));
+
+    let capped = LzCoder::new().max_output(data.len() as u64 - 1);
+    assert_eq!(capped.decode(&encoded), Err(CodecError::LimitExceeded));
+    assert_eq!(LzCoder::new().max_output(data.len() as u64).decode(&encoded).unwrap(), data);
+    assert_eq!(coder.decode_capped(&encoded, data.len() as u64 - 1), Err(CodecError::LimitExceeded));
+}
@@ -0,0 +1,171 @@
+use rbtcore::CodecError;
+use rbtcore::alpha_flow::{AlphaFlowEncode, NoopCoder};
+use rbtcore::ledger::{self, ReadOptions};
+use rbtcore::stream::{Decoder, Encoder, StreamOptions, decode_stream, encode_stream};
+use std::io::{self, Read, Write};
+
+fn random_bytes(len: usize, mut seed: u64) -> Vec<u8> {
+    (0..len)
+        .map(|_| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed >> 32) as u8 & 3
+        })
+        .collect()
+}
+
+/// Run-length coder over ledgers, so blocks actually change size.
+struct LedgerCoder;
+
+impl AlphaFlowEncode for LedgerCoder {
+    fn encode(&self, input: &[u8]) -> Vec<u8> {
+        ledger::serialize_smallest(&ledger::encode(input))
+    }
+
+    fn decode(&self, compressed: &[u8]) -> Result<Vec<u8>, CodecError> {
+        self.decode_capped(compressed, 1 << 20)
+    }
+
+    fn decode_capped(&self, compressed: &[u8], max_len: u64) -> Result<Vec<u8>, CodecError> {
+        let options = ReadOptions::new().max_output(max_len);
+        Ok(ledger::decode_with(&ledger::deserialize_with(compressed, &options)?, &options)?)
+    }
+}
+
+fn encode_all(data: &[u8], options: StreamOptions) -> Vec<u8> {
+    let mut out = Vec::new();
+    encode_stream(LedgerCoder, &mut &data[..], &mut out, options).unwrap();
+    out
+}
+
+fn decode_all(encoded: &[u8], options: StreamOptions) -> io::Result<Vec<u8>> {
+    let mut out = Vec::new();
+    decode_stream(LedgerCoder, &mut &encoded[..], &mut out, options)?;
+    Ok(out)
+}
+
+#[test]
+fn roundtrip_across_block_sizes() {
+    let data = random_bytes(100_000, 1);
+    for block_size in [1, 7, 4096, 100_000, 1 << 20] {
+        let options = StreamOptions::new().block_size(block_size);
+        let encoded = encode_all(&data, options);
+        assert_eq!(decode_all(&encoded, options).unwrap(), data, "block size {block_size}");
+    }
+    let empty = encode_all(&[], StreamOptions::new());
+    assert_eq!(empty, [0, 0, 0, 0]);
+    assert!(decode_all(&empty, StreamOptions::new()).unwrap().is_empty());
+}
+
+#[test]
+fn small_writes_and_reads_match_one_shot() {
+    let data = random_bytes(50_000, 2);
+    let options = StreamOptions::new().block_size(8192);
+    let mut encoder = Encoder::with_options(LedgerCoder, Vec::new(), options);
+    for piece in data.chunks(333) {
+        encoder.write_all(piece).unwrap();
+    }
+    let encoded = encoder.finish().unwrap();
+    assert_eq!(encoded, encode_all(&data, options));
+
+    let mut decoder = Decoder::with_options(LedgerCoder, &encoded[..], options);
+    let mut out = Vec::new();
+    let mut buf = [0u8; 100];
+    loop {
+        let n = decoder.read(&mut buf).unwrap();
+        if n == 0 {
+            break;
+        }
+        out.extend_from_slice(&buf[..n]);
+    }
+    assert_eq!(out, data);
+}
+
+#[test]
+fn flush_makes_written_bytes_decodable() {
+    let mut encoder = Encoder::new(NoopCoder, Vec::new());
+    encoder.write_all(b"first").unwrap();
+    encoder.flush().unwrap();
+    let mut partial = encoder.get_ref().clone();
+    partial.extend([0, 0, 0, 0]);
+    let mut out = Vec::new();
+    Decoder::new(NoopCoder, &partial[..]).read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"first");
+
+    encoder.write_all(b" second").unwrap();
+    let encoded = encoder.finish().unwrap();
+    out.clear();
+    Decoder::new(NoopCoder, &encoded[..]).read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"first second");
+}
+
+#[test]
+fn leaves_trailing_bytes_unread() {
+    let mut encoded = encode_all(b"aaaabbbb", StreamOptions::new());
+    encoded.extend_from_slice(b"tail");
+    let mut decoder = Decoder::new(LedgerCoder, &encoded[..]);
+    let mut out = Vec::new();
+    decoder.read_to_end(&mut out).unwrap();
+    assert_eq!(out, b"aaaabbbb");
+    assert_eq!(decoder.into_inner(), b"tail");
+}
+
+#[test]
+fn truncation_is_unexpected_eof() {
+    let data = random_bytes(3_000, 3);
+    let options = StreamOptions::new().block_size(1024);
+    let encoded = encode_all(&data, options);
+    for len in 0..encoded.len() {
+        let err = decode_all(&encoded[..len], options).unwrap_err();
+        let codec = err.get_ref().and_then(|e| e.downcast_ref::<CodecError>());
+        assert!(matches!(codec, Some(CodecError::Truncated | CodecError::Corrupt)), "len {len}: {err}");
+    }
+    // A stream cut at a frame boundary lacks the end frame.
+    let err = decode_all(&encoded[..encoded.len() - 4], options).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::UnexpectedEof);
+}
+
+#[test]
+fn enforces_block_and_output_limits() {
+    let data = random_bytes(10_000, 4);
+    let encoded = encode_all(&data, StreamOptions::new().block_size(4096));
+
+    let err = decode_all(&encoded, StreamOptions::new().block_size(1024)).unwrap_err();
+    assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+    let options = StreamOptions::new().max_output(9_999);
+    let mut decoder = Decoder::with_options(LedgerCoder, &encoded[..], options);
+    let mut out = Vec::new();
+    let err = decoder.read_to_end(&mut out).unwrap_err();
+    assert_eq!(err.get_ref().unwrap().downcast_ref::<CodecError>(), Some(&CodecError::LimitExceeded));
+    // Only whole blocks below the limit were produced, and the error sticks.
+    assert_eq!(out, data[..8192]);
+    assert_eq!(decoder.read(&mut [0; 8]).unwrap_err().kind(), io::ErrorKind::InvalidData);
+    assert_eq!(decode_all(&encoded, StreamOptions::new().max_output(10_000)).unwrap(), data);
+}
+
+#[test]
+fn rejects_blocks_of_the_wrong_length() {
+    // The frame says 3 bytes, the coder produces 4.
+    let frame = [3, 0, 0, 0, 4, 0, 0, 0, 1, 2, 3, 4, 0, 0, 0, 0];
+    let mut out = Vec::new();
+    let err = Decoder::new(NoopCoder, &frame[..]).read_to_end(&mut out).unwrap_err();
+    assert_eq!(err.get_ref().unwrap().downcast_ref::<CodecError>(), Some(&CodecError::Corrupt));
+}
+
+#[test]
+fn frames_cannot_outgrow_their_header() {
+    // One byte declared, but the ledger payload expands to 4 GiB; the
+    // decoder must stop at the declared length rather than allocate it.
+    let mut frame = vec![1, 0, 0, 0, 9, 0, 0, 0];
+    frame.extend_from_slice(&[1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff, b'x']);
+    frame.extend_from_slice(&[0; 4]);
+    let err = Decoder::new(LedgerCoder, &frame[..]).read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.get_ref().unwrap().downcast_ref::<CodecError>(), Some(&CodecError::Corrupt));
+
+    // A payload length far beyond what one byte can encode to is rejected
+    // before it is read.
+    let frame = [1, 0, 0, 0, 0xff, 0xff, 0xff, 0xff];
+    let err = Decoder::new(NoopCoder, &frame[..]).read_to_end(&mut Vec::new()).unwrap_err();
+    assert_eq!(err.get_ref().unwrap().downcast_ref::<CodecError>(), Some(&CodecError::Corrupt));
+}
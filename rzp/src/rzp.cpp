@@ -32,12 +32,18 @@ std::vector<uint8_t> read_file(const std::string& path) {
 
 std::vector<uint8_t> encode_container(const std::vector<uint8_t>& raw) {
     auto ledger = ledgerizer::encode(raw);
-    auto ser = ledgerizer::serialize(ledger);
+    auto ser = ledgerizer::serialize_smallest(ledger);
 
-    uint32_t ansx_len = 0;
-    uint8_t* ansx_ptr = ansx_encode(ser.data(), static_cast<uint32_t>(ser.size()), &ansx_len);
+    size_t ansx_len = 0;
+    uint8_t* ansx_ptr = ansx_encode64(ser.data(), ser.size(), &ansx_len);
+    if (!ansx_ptr) {
+        throw std::runtime_error("ANS-X encode failed");
+    }
     std::vector<uint8_t> ansx_bytes(ansx_ptr, ansx_ptr + ansx_len);
-    ansx_free(ansx_ptr, ansx_len);
+    ansx_free64(ansx_ptr, ansx_len);
+    if (ansx_bytes.size() > UINT32_MAX) {
+        throw std::runtime_error("Input too large for container (compressed size exceeds 4 GiB)");
+    }
 
     uint32_t crc = rzp::crc32(ansx_bytes);
     rzp::Sha256 hasher;
@@ -81,10 +87,14 @@ std::vector<uint8_t> decode_container(const std::vector<uint8_t>& container) {
     if (crc != crc_calc) {
         throw std::runtime_error("CRC mismatch");
     }
-    uint32_t ser_len = 0;
-    uint8_t* ser_ptr = ansx_decode(ansx_bytes.data(), len, &ser_len);
+    uint8_t* ser_ptr = nullptr;
+    size_t ser_len = 0;
+    int status = ansx_decode2_64(ansx_bytes.data(), len, &ser_ptr, &ser_len);
+    if (status != ANSX_OK) {
+        throw std::runtime_error("ANS-X decode failed (status " + std::to_string(status) + ")");
+    }
     std::vector<uint8_t> ser(ser_ptr, ser_ptr + ser_len);
-    ansx_free(ser_ptr, ser_len);
+    ansx_free64(ser_ptr, ser_len);
     auto ledger = ledgerizer::deserialize(ser);
     auto raw = ledgerizer::decode(ledger);
     rzp::Sha256 hasher;